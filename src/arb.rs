@@ -0,0 +1,88 @@
+//! Flutter Application Resource Bundle (`.arb`) import and export.
+//!
+//! An ARB file is a flat JSON object of messages. `@key` entries hold the
+//! metadata of `key` (description, placeholders, ...) and `@@name` entries hold
+//! file-level attributes such as `@@locale`. Metadata is kept next to the
+//! translations rather than imported as messages.
+
+use std::collections::HashMap;
+use serde_json::{Map, Value};
+use crate::helpers::map_leaves;
+use crate::metadata::KeyMetadata;
+use crate::TranslationValue;
+
+/// The metadata key under which file-level `@@` attributes are kept.
+pub const FILE_METADATA_KEY: &str = "@@";
+
+pub struct ArbDocument {
+    pub locale: Option<String>,
+    pub messages: Vec<(String, String)>,
+    /// Per-key metadata, plus file-level attributes under [`FILE_METADATA_KEY`].
+    pub metadata: Vec<(String, KeyMetadata)>,
+}
+
+pub fn parse_arb(text: &str) -> Result<ArbDocument, String> {
+    let object: Map<String, Value> = serde_json::from_str(text).map_err(|e| format!("Invalid ARB: {}", e))?;
+
+    let mut document = ArbDocument {
+        locale: None,
+        messages: Vec::new(),
+        metadata: Vec::new(),
+    };
+    let mut attributes = Map::new();
+    for (key, value) in object {
+        if let Some(attribute) = key.strip_prefix("@@") {
+            match (attribute, value) {
+                ("locale", Value::String(locale)) => document.locale = Some(locale),
+                (attribute, value) => {
+                    attributes.insert(attribute.to_string(), value);
+                }
+            }
+        } else if let Some(message) = key.strip_prefix('@') {
+            match value {
+                Value::Object(metadata) => document.metadata.push((message.to_string(), KeyMetadata::from_object(metadata)?)),
+                _ => return Err(format!("Invalid ARB: metadata '{}' must be an object", key)),
+            }
+        } else {
+            match value {
+                Value::String(text) => document.messages.push((key, text)),
+                _ => return Err(format!("Invalid ARB: message '{}' must be a string", key)),
+            }
+        }
+    }
+    if !attributes.is_empty() {
+        document.metadata.push((FILE_METADATA_KEY.to_string(), KeyMetadata::from_object(attributes)?));
+    }
+    Ok(document)
+}
+
+/// Writes a locale as an ARB document. Nested keys are flattened to dotted
+/// names and every message is followed by its `@key` metadata, if any.
+pub fn write_arb(locale: &str, map: &HashMap<String, TranslationValue>, metadata: Option<&HashMap<String, KeyMetadata>>) -> String {
+    let mut entries = vec![("@@locale".to_string(), Value::from(locale))];
+    if let Some(attributes) = metadata.and_then(|metadata| metadata.get(FILE_METADATA_KEY)) {
+        for (attribute, value) in attributes.to_object() {
+            entries.push((format!("@@{}", attribute), value));
+        }
+    }
+
+    let mut leaves = map_leaves(map);
+    leaves.sort();
+    for (key, text) in leaves {
+        let meta = metadata.and_then(|metadata| metadata.get(&key));
+        entries.push((key.clone(), Value::from(text)));
+        if let Some(meta) = meta {
+            entries.push((format!("@{}", key), Value::Object(meta.to_object())));
+        }
+    }
+
+    // Written by hand so that each `@key` entry stays next to its message.
+    let mut out = String::from("{\n");
+    for (i, (key, value)) in entries.iter().enumerate() {
+        let value = serde_json::to_string_pretty(value).unwrap_or_default();
+        out.push_str(&format!("  {}: {}", Value::from(key.as_str()), value.replace('\n', "\n  ")));
+        out.push_str(if i + 1 < entries.len() { ",\n" } else { "\n" });
+    }
+    out.push_str("}\n");
+    out
+}
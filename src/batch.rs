@@ -0,0 +1,76 @@
+//! Batches of set/update/delete operations applied all-or-nothing.
+//!
+//! Operations run against copies of the locales they touch; the copies
+//! replace the live maps only when every operation succeeded, so a failing
+//! batch leaves the store as it was.
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::helpers::{get_value_by_key, remove_translation_recursive, split_key, update_translation_recursive};
+use crate::TranslationValue;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum BatchOp {
+    /// Writes `value` at `key`, creating the locale if needed.
+    Set { locale: String, key: String, value: TranslationValue },
+    /// Writes `value` at `key` of an existing locale.
+    Update { locale: String, key: String, value: TranslationValue },
+    /// Removes `key`, which must exist.
+    Delete { locale: String, key: String },
+}
+
+impl BatchOp {
+    pub fn locale(&self) -> &str {
+        match self {
+            BatchOp::Set { locale, .. } | BatchOp::Update { locale, .. } | BatchOp::Delete { locale, .. } => locale,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        match self {
+            BatchOp::Set { key, .. } | BatchOp::Update { key, .. } | BatchOp::Delete { key, .. } => key,
+        }
+    }
+}
+
+/// Applies `ops` in order to `working`, which holds copies of the touched
+/// locales that already exist. Fails with the index of the first operation
+/// that cannot be applied.
+pub fn apply_ops(
+    working: &mut HashMap<String, HashMap<String, TranslationValue>>,
+    ops: &[BatchOp],
+) -> Result<(), String> {
+    for (i, op) in ops.iter().enumerate() {
+        apply_op(working, op).map_err(|e| format!("Operation {} ({} '{}'): {}", i, op_name(op), op.key(), e))?;
+    }
+    Ok(())
+}
+
+fn apply_op(working: &mut HashMap<String, HashMap<String, TranslationValue>>, op: &BatchOp) -> Result<(), String> {
+    let keys = split_key(op.key());
+    match op {
+        BatchOp::Set { locale, value, .. } => {
+            let map = working.entry(locale.clone()).or_default();
+            update_translation_recursive(map, &keys, value.clone())
+        }
+        BatchOp::Update { locale, value, .. } => {
+            let map = working.get_mut(locale).ok_or("Locale not found")?;
+            update_translation_recursive(map, &keys, value.clone())
+        }
+        BatchOp::Delete { locale, key } => {
+            let map = working.get_mut(locale).ok_or("Locale not found")?;
+            get_value_by_key(map, key)?;
+            remove_translation_recursive(map, &keys, false);
+            Ok(())
+        }
+    }
+}
+
+fn op_name(op: &BatchOp) -> &'static str {
+    match op {
+        BatchOp::Set { .. } => "set",
+        BatchOp::Update { .. } => "update",
+        BatchOp::Delete { .. } => "delete",
+    }
+}
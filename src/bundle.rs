@@ -0,0 +1,215 @@
+//! Compact binary bundle format.
+//!
+//! Layout (all integers are unsigned LEB128 varints):
+//!
+//! ```text
+//! magic   "WI18"
+//! version u8
+//! strings count, then `len + utf8 bytes` per string
+//! locales count, then per locale: locale string id and its map node
+//!
+//! node    `value id << 1` for a string, or `entry count << 1 | 1` for a map,
+//!         followed by `key id + node` per entry
+//! ```
+//!
+//! The tree is stored as is, so literal dotted keys and empty maps survive a
+//! round trip. Decoding never has to parse JSON: every string is read straight
+//! from the input buffer into the tree it belongs to.
+
+use std::collections::HashMap;
+use crate::TranslationValue;
+
+pub const BUNDLE_MAGIC: &[u8; 4] = b"WI18";
+pub const BUNDLE_VERSION: u8 = 1;
+
+/// One decoded locale: its name and translation tree.
+pub type BundleLocale = (String, HashMap<String, TranslationValue>);
+
+/// Encodes `locale → translation tree` into a binary bundle.
+pub fn encode_bundle(translations: &HashMap<String, HashMap<String, TranslationValue>>) -> Vec<u8> {
+    let mut table = StringTable::default();
+    let mut body = Vec::new();
+
+    let mut names: Vec<&String> = translations.keys().collect();
+    names.sort();
+    write_varint(&mut body, names.len() as u32);
+    for locale in names {
+        write_varint(&mut body, table.id(locale));
+        write_map(&mut body, &mut table, &translations[locale]);
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(BUNDLE_MAGIC);
+    out.push(BUNDLE_VERSION);
+    write_varint(&mut out, table.strings.len() as u32);
+    for s in &table.strings {
+        write_varint(&mut out, s.len() as u32);
+        out.extend_from_slice(s.as_bytes());
+    }
+    out.extend_from_slice(&body);
+    out
+}
+
+fn write_map(out: &mut Vec<u8>, table: &mut StringTable, map: &HashMap<String, TranslationValue>) {
+    let mut entries: Vec<(&String, &TranslationValue)> = map.iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+    write_varint(out, ((entries.len() as u32) << 1) | 1);
+    for (key, value) in entries {
+        write_varint(out, table.id(key));
+        match value {
+            TranslationValue::String(text) => write_varint(out, table.id(text) << 1),
+            TranslationValue::Nested(map) => write_map(out, table, map),
+        }
+    }
+}
+
+/// Encodes a JSON document shaped like `{ "en": { "hello": "Hello" }, ... }`.
+pub fn encode_bundle_json(json: &str) -> Result<Vec<u8>, String> {
+    let translations: HashMap<String, HashMap<String, TranslationValue>> =
+        serde_json::from_str(json).map_err(|e| format!("Invalid translations JSON: {}", e))?;
+    Ok(encode_bundle(&translations))
+}
+
+/// Decodes a binary bundle into complete translation trees, in bundle order.
+pub fn decode_bundle(bytes: &[u8]) -> Result<Vec<BundleLocale>, String> {
+    let mut reader = Reader::new(bytes);
+
+    if reader.take(BUNDLE_MAGIC.len())? != BUNDLE_MAGIC {
+        return Err("Invalid bundle: bad magic".to_string());
+    }
+    let version = reader.take(1)?[0];
+    if version != BUNDLE_VERSION {
+        return Err(format!("Unsupported bundle version {}", version));
+    }
+
+    let count = reader.varint()? as usize;
+    let mut strings = Vec::with_capacity(count.min(bytes.len()));
+    for _ in 0..count {
+        let len = reader.varint()? as usize;
+        let raw = reader.take(len)?;
+        strings.push(std::str::from_utf8(raw).map_err(|_| "Invalid bundle: string is not UTF-8".to_string())?);
+    }
+    let table = Strings(strings);
+
+    let locale_count = reader.varint()? as usize;
+    let mut locales = Vec::with_capacity(locale_count.min(bytes.len()));
+    for _ in 0..locale_count {
+        let locale = table.get(reader.varint()?)?.to_string();
+        let map = match read_node(&mut reader, &table, 0)? {
+            TranslationValue::Nested(map) => map,
+            TranslationValue::String(_) => {
+                return Err(format!("Invalid bundle: locale '{}' is not a map", locale));
+            }
+        };
+        locales.push((locale, map));
+    }
+
+    if reader.pos != bytes.len() {
+        return Err("Invalid bundle: trailing bytes".to_string());
+    }
+    Ok(locales)
+}
+
+/// Deepest map nesting accepted when decoding, so a corrupt bundle cannot
+/// exhaust the stack.
+const MAX_DEPTH: usize = 128;
+
+fn read_node(reader: &mut Reader<'_>, table: &Strings<'_>, depth: usize) -> Result<TranslationValue, String> {
+    let tag = reader.varint()?;
+    if tag & 1 == 0 {
+        return Ok(TranslationValue::String(table.get(tag >> 1)?.to_string()));
+    }
+    if depth >= MAX_DEPTH {
+        return Err("Invalid bundle: maps nested too deeply".to_string());
+    }
+    let count = (tag >> 1) as usize;
+    let mut map = HashMap::with_capacity(count.min(reader.remaining().len()));
+    for _ in 0..count {
+        let key = table.get(reader.varint()?)?.to_string();
+        let value = read_node(reader, table, depth + 1)?;
+        map.insert(key, value);
+    }
+    Ok(TranslationValue::Nested(map))
+}
+
+struct Strings<'a>(Vec<&'a str>);
+
+impl<'a> Strings<'a> {
+    fn get(&self, id: u32) -> Result<&'a str, String> {
+        self.0
+            .get(id as usize)
+            .copied()
+            .ok_or_else(|| format!("Invalid bundle: string id {} out of range", id))
+    }
+}
+
+#[derive(Default)]
+struct StringTable {
+    strings: Vec<String>,
+    ids: HashMap<String, u32>,
+}
+
+impl StringTable {
+    fn id(&mut self, s: &str) -> u32 {
+        if let Some(id) = self.ids.get(s) {
+            return *id;
+        }
+        let id = self.strings.len() as u32;
+        self.strings.push(s.to_string());
+        self.ids.insert(s.to_string(), id);
+        id
+    }
+}
+
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes, pos: 0 }
+    }
+
+    /// The bytes that have not been read yet.
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.bytes[self.pos..]
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| "Invalid bundle: unexpected end of data".to_string())?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    pub fn varint(&mut self) -> Result<u32, String> {
+        let mut result: u32 = 0;
+        for shift in (0..35).step_by(7) {
+            let byte = self.take(1)?[0];
+            result |= ((byte & 0x7f) as u32)
+                .checked_shl(shift)
+                .ok_or_else(|| "Invalid bundle: varint overflow".to_string())?;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err("Invalid bundle: varint overflow".to_string())
+    }
+}
+
+pub fn write_varint(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
@@ -0,0 +1,135 @@
+//! Translation coverage of every locale against a source locale.
+//!
+//! Source keys are dotted paths to string leaves, with plural maps counted as
+//! a single key as in validation. A key is translated in a locale when the
+//! locale has a non-empty value of the same shape at its path, where a string
+//! and a plural map count as the same shape. Namespaces are the top-level
+//! keys of the source locale.
+
+use std::collections::{BTreeMap, HashMap};
+use serde::Serialize;
+use crate::helpers::{escape_segment, get_value_by_key, is_plural_map, join_key};
+use crate::TranslationValue;
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CoverageReport {
+    pub source_locale: String,
+    pub total_keys: usize,
+    pub total_words: usize,
+    pub locales: BTreeMap<String, Coverage>,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Coverage {
+    pub total_keys: usize,
+    pub translated_keys: usize,
+    /// Share of the source keys translated, from 0 to 100.
+    pub percent: f64,
+    /// Words of the source strings, and of those still untranslated.
+    pub total_words: usize,
+    pub untranslated_words: usize,
+    pub untranslated: Vec<String>,
+    /// Coverage per top-level key; empty for the namespaces themselves.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub namespaces: BTreeMap<String, Coverage>,
+}
+
+impl Coverage {
+    fn add(&mut self, key: &str, words: usize, translated: bool) {
+        self.total_keys += 1;
+        self.total_words += words;
+        if translated {
+            self.translated_keys += 1;
+        } else {
+            self.untranslated_words += words;
+            self.untranslated.push(key.to_string());
+        }
+    }
+
+    fn finish(&mut self) {
+        self.percent = if self.total_keys == 0 {
+            100.0
+        } else {
+            (self.translated_keys as f64 * 10000.0 / self.total_keys as f64).round() / 100.0
+        };
+        self.untranslated.sort();
+        for namespace in self.namespaces.values_mut() {
+            namespace.finish();
+        }
+    }
+}
+
+/// Computes the coverage of every locale in `locales` against `source_locale`.
+pub fn coverage(
+    source_locale: &str,
+    locales: &HashMap<String, HashMap<String, TranslationValue>>,
+) -> Result<CoverageReport, String> {
+    let source = locales.get(source_locale).ok_or("Locale not found")?;
+    let mut keys = Vec::new();
+    let mut namespaces = Vec::new();
+    for (namespace, value) in source {
+        let start = keys.len();
+        source_keys(&escape_segment(namespace), value, &mut keys);
+        namespaces.extend(std::iter::repeat_n(namespace.as_str(), keys.len() - start));
+    }
+
+    let mut report = CoverageReport {
+        source_locale: source_locale.to_string(),
+        total_keys: keys.len(),
+        total_words: keys.iter().map(|(_, words, _)| words).sum(),
+        ..CoverageReport::default()
+    };
+    for (locale, map) in locales {
+        let mut coverage = Coverage::default();
+        for ((key, words, plural), namespace) in keys.iter().zip(&namespaces) {
+            let translated = get_value_by_key(map, key).is_ok_and(|value| is_translated(value, *plural));
+            coverage.namespaces.entry(namespace.to_string()).or_default().add(key, *words, translated);
+            coverage.add(key, *words, translated);
+        }
+        coverage.finish();
+        report.locales.insert(locale.clone(), coverage);
+    }
+    Ok(report)
+}
+
+/// Collects `(key, word count, is plural map)` for every string leaf and
+/// plural map under `value`, which lives at `path`.
+fn source_keys(path: &str, value: &TranslationValue, out: &mut Vec<(String, usize, bool)>) {
+    match value {
+        TranslationValue::String(text) => out.push((path.to_string(), word_count(text), false)),
+        TranslationValue::Nested(map) if is_plural_map(map) => {
+            let words = map.values().map(words_in).sum();
+            out.push((path.to_string(), words, true));
+        }
+        TranslationValue::Nested(map) => {
+            for (key, child) in map {
+                source_keys(&join_key(path, key), child, out);
+            }
+        }
+    }
+}
+
+fn words_in(value: &TranslationValue) -> usize {
+    match value {
+        TranslationValue::String(text) => word_count(text),
+        TranslationValue::Nested(map) => map.values().map(words_in).sum(),
+    }
+}
+
+/// Whitespace-separated runs that contain a letter or digit.
+fn word_count(text: &str) -> usize {
+    text.split_whitespace().filter(|word| word.chars().any(char::is_alphanumeric)).count()
+}
+
+/// Whether `value` translates a source key, which is a plural map when
+/// `plural`. Any other map in place of a string is a shape conflict, as
+/// validation reports it, and does not count.
+fn is_translated(value: &TranslationValue, plural: bool) -> bool {
+    match value {
+        TranslationValue::String(text) => !text.trim().is_empty(),
+        TranslationValue::Nested(map) if plural || is_plural_map(map) => map.values().any(|form| is_translated(form, true)),
+        TranslationValue::Nested(_) => false,
+    }
+}
@@ -0,0 +1,126 @@
+//! Change events and the JS listeners subscribed to them.
+//!
+//! Mutations record the dotted keys of the strings they write or remove while
+//! the translations lock is held; `I18n` emits them once the lock is released,
+//! so listeners can read from the instance.
+
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use serde::Serialize;
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::JsValue;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Event {
+    Change,
+    LocaleChanged,
+    Loaded,
+}
+
+impl Event {
+    pub fn parse(name: &str) -> Result<Event, String> {
+        match name {
+            "change" => Ok(Event::Change),
+            "localeChanged" => Ok(Event::LocaleChanged),
+            "loaded" => Ok(Event::Loaded),
+            other => Err(format!("Unknown event '{}'", other)),
+        }
+    }
+}
+
+/// Payload of `change`: the string keys of one locale that were written or
+/// removed.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeEvent {
+    pub locale: String,
+    pub keys: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleChangedEvent {
+    pub locale: String,
+    pub previous: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedEvent {
+    pub locales: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[derive(Default)]
+pub struct Listeners {
+    next_id: u32,
+    entries: Vec<(u32, Event, js_sys::Function)>,
+}
+
+impl Listeners {
+    pub fn add(&mut self, event: Event, callback: js_sys::Function) -> u32 {
+        self.next_id += 1;
+        self.entries.push((self.next_id, event, callback));
+        self.next_id
+    }
+
+    pub fn remove(&mut self, id: u32) {
+        self.entries.retain(|(entry, _, _)| *entry != id);
+    }
+
+    pub fn has(&self, event: Event) -> bool {
+        self.entries.iter().any(|(_, entry, _)| *entry == event)
+    }
+
+    /// The callbacks for `event`, cloned so they can subscribe or unsubscribe
+    /// while being called.
+    pub fn callbacks(&self, event: Event) -> Vec<js_sys::Function> {
+        self.entries
+            .iter()
+            .filter(|(_, entry, _)| *entry == event)
+            .map(|(_, _, callback)| callback.clone())
+            .collect()
+    }
+}
+
+/// Calls every listener of `event` with `payload`. A listener that throws is
+/// reported on the console and does not stop the others.
+pub fn emit(listeners: &RefCell<Listeners>, event: Event, payload: &JsValue) {
+    let callbacks = listeners.borrow().callbacks(event);
+    for callback in callbacks {
+        if let Err(error) = callback.call1(&JsValue::NULL, payload) {
+            web_sys::console::error_1(&error);
+        }
+    }
+}
+
+/// Handle returned by `I18n.on`.
+#[wasm_bindgen]
+pub struct Subscription {
+    listeners: Weak<RefCell<Listeners>>,
+    id: u32,
+}
+
+impl Subscription {
+    pub fn new(listeners: &Rc<RefCell<Listeners>>, id: u32) -> Subscription {
+        Subscription { listeners: Rc::downgrade(listeners), id }
+    }
+}
+
+#[wasm_bindgen]
+impl Subscription {
+    /// Removes the listener. Calling it again, or after the `I18n` instance
+    /// is gone, does nothing.
+    ///
+    /// # Example
+    /// ```js
+    /// const subscription = i18n.on('change', render);
+    /// subscription.unsubscribe();
+    /// ```
+    pub fn unsubscribe(&self) {
+        if let Some(listeners) = self.listeners.upgrade() {
+            listeners.borrow_mut().remove(self.id);
+        }
+    }
+}
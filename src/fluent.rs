@@ -0,0 +1,560 @@
+//! Project Fluent (`.ftl`) resources.
+//!
+//! Messages are stored under their identifier and terms under `-identifier`,
+//! keeping the Fluent pattern source as the translation string. A message with
+//! attributes becomes a nested map of its attributes, with its own value (if
+//! any) under `_`. Patterns are evaluated at format time, so select
+//! expressions, term references and message references all see the same args
+//! `formatTranslation` receives.
+
+use std::collections::HashMap;
+use crate::helpers::{get_value_by_key, plural_category, FormatArg};
+use crate::TranslationValue;
+
+/// Key holding a message's own value when it also has attributes.
+pub const VALUE_KEY: &str = "_";
+
+const MAX_DEPTH: usize = 32;
+
+/// Parses a `.ftl` resource into `(key, value)` pairs ready to be inserted.
+pub fn parse_resource(text: &str) -> Result<Vec<(String, TranslationValue)>, String> {
+    let mut entries = Vec::new();
+    let mut current: Option<RawEntry> = None;
+    let mut blank_lines = 0;
+
+    for (number, line) in text.lines().enumerate() {
+        let error = |msg: &str| format!("FTL parse error on line {}: {}", number + 1, msg);
+
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+
+        // A select expression may be closed by a `}` at the start of a line.
+        if line.starts_with([' ', '\t', '}']) {
+            let entry = current.as_mut().ok_or_else(|| error("indented line outside of an entry"))?;
+            let trimmed = line.trim_start();
+            if let Some((name, rest)) = trimmed.strip_prefix('.').and_then(|attr| attr.split_once('=')) {
+                let name = name.trim();
+                if !is_identifier(name) {
+                    return Err(error(&format!("invalid attribute name '{}'", name)));
+                }
+                entry.attributes.push((name.to_string(), vec![rest.trim_start().to_string()]));
+            } else {
+                let target = match entry.attributes.last_mut() {
+                    Some((_, lines)) => lines,
+                    None => &mut entry.value,
+                };
+                target.extend((0..blank_lines).map(|_| String::new()));
+                target.push(line.to_string());
+            }
+            blank_lines = 0;
+            continue;
+        }
+        blank_lines = 0;
+
+        if let Some(entry) = current.take() {
+            entries.push(entry.finish()?);
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let (id, rest) = line
+            .split_once('=')
+            .ok_or_else(|| error("expected 'identifier = value'"))?;
+        let id = id.trim();
+        let name = id.strip_prefix('-').unwrap_or(id);
+        if !is_identifier(name) {
+            return Err(error(&format!("invalid identifier '{}'", id)));
+        }
+        current = Some(RawEntry {
+            id: id.to_string(),
+            value: vec![rest.trim_start().to_string()],
+            attributes: Vec::new(),
+        });
+    }
+
+    if let Some(entry) = current {
+        entries.push(entry.finish()?);
+    }
+    Ok(entries)
+}
+
+struct RawEntry {
+    id: String,
+    value: Vec<String>,
+    attributes: Vec<(String, Vec<String>)>,
+}
+
+impl RawEntry {
+    fn finish(self) -> Result<(String, TranslationValue), String> {
+        let value = dedent(&self.value);
+        if self.attributes.is_empty() {
+            return match value {
+                Some(value) => Ok((self.id, TranslationValue::String(value))),
+                None => Err(format!("FTL entry '{}' has no value", self.id)),
+            };
+        }
+
+        let mut map = HashMap::new();
+        if let Some(value) = value {
+            map.insert(VALUE_KEY.to_string(), TranslationValue::String(value));
+        }
+        for (name, lines) in self.attributes {
+            let value = match dedent(&lines) {
+                Some(value) => value,
+                None => return Err(format!("FTL attribute '{}.{}' has no value", self.id, name)),
+            };
+            map.insert(name, TranslationValue::String(value));
+        }
+        Ok((self.id, TranslationValue::Nested(map)))
+    }
+}
+
+/// Joins a pattern's lines, dropping the common indentation of the block lines.
+fn dedent(lines: &[String]) -> Option<String> {
+    let (first, block) = lines.split_first()?;
+    let indent = block
+        .iter()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('}'))
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut parts = Vec::with_capacity(lines.len());
+    if !first.trim().is_empty() {
+        parts.push(first.trim_end());
+    }
+    for line in block {
+        let strip = line.len() - line.trim_start().len();
+        parts.push(line[strip.min(indent)..].trim_end());
+    }
+    let joined = parts.join("\n");
+    let joined = joined.trim_matches('\n');
+    if joined.is_empty() {
+        None
+    } else {
+        Some(joined.to_string())
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[derive(Debug, Clone)]
+pub enum Node {
+    Text(String),
+    Placeable(Expr),
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Str(String),
+    Num(f64, String),
+    Var(String),
+    Message { id: String, attr: Option<String> },
+    Term { id: String, attr: Option<String>, args: Vec<(String, Expr)> },
+    Call { name: String, args: Vec<Expr> },
+    Select { selector: Box<Expr>, variants: Vec<Variant> },
+}
+
+#[derive(Debug, Clone)]
+pub struct Variant {
+    pub key: String,
+    pub default: bool,
+    pub pattern: Vec<Node>,
+}
+
+/// Parses a stored pattern into its nodes.
+pub fn parse_pattern(src: &str) -> Result<Vec<Node>, String> {
+    let mut parser = Parser { src, pos: 0 };
+    let nodes = parser.pattern(false)?;
+    if parser.pos < src.len() {
+        return Err(format!("Unexpected '{}' in Fluent pattern", &src[parser.pos..]));
+    }
+    Ok(nodes)
+}
+
+/// Positional and named arguments of a function call or term reference.
+type CallArguments = (Vec<Expr>, Vec<(String, Expr)>);
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        if self.rest().starts_with(s) {
+            self.pos += s.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_blank(&mut self) {
+        while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), String> {
+        if self.eat(s) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}' in Fluent pattern at '{}'", s, self.rest()))
+        }
+    }
+
+    /// Parses text and placeables. Inside a variant, stops before the line
+    /// that starts the next variant or closes the select expression.
+    fn pattern(&mut self, in_variant: bool) -> Result<Vec<Node>, String> {
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+
+        while let Some(c) = self.peek() {
+            match c {
+                '{' => {
+                    self.bump();
+                    if !text.is_empty() {
+                        nodes.push(Node::Text(std::mem::take(&mut text)));
+                    }
+                    let expr = self.expression()?;
+                    self.skip_blank();
+                    self.expect("}")?;
+                    nodes.push(Node::Placeable(expr));
+                }
+                '}' if in_variant => break,
+                '\n' if in_variant => {
+                    let next = self.rest()[1..].trim_start();
+                    if next.starts_with('[') || next.starts_with("*[") || next.starts_with('}') {
+                        break;
+                    }
+                    self.bump();
+                    text.push('\n');
+                }
+                _ => {
+                    self.bump();
+                    text.push(c);
+                }
+            }
+        }
+
+        if in_variant {
+            let trimmed = text.trim_end().len();
+            text.truncate(trimmed);
+        }
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+        Ok(nodes)
+    }
+
+    fn expression(&mut self) -> Result<Expr, String> {
+        self.skip_blank();
+        let expr = self.inline_expression()?;
+        self.skip_blank();
+        if !self.eat("->") {
+            return Ok(expr);
+        }
+
+        let mut variants = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.peek() == Some('}') || self.peek().is_none() {
+                break;
+            }
+            let default = self.eat("*");
+            self.expect("[")?;
+            let end = self
+                .rest()
+                .find(']')
+                .ok_or_else(|| "Unterminated variant key in Fluent pattern".to_string())?;
+            let key = self.rest()[..end].trim().to_string();
+            self.pos += end + 1;
+            while matches!(self.peek(), Some(' ') | Some('\t')) {
+                self.bump();
+            }
+            let pattern = self.pattern(true)?;
+            variants.push(Variant { key, default, pattern });
+        }
+
+        if variants.iter().filter(|v| v.default).count() != 1 {
+            return Err("Fluent select expression needs exactly one default variant".to_string());
+        }
+        Ok(Expr::Select { selector: Box::new(expr), variants })
+    }
+
+    fn inline_expression(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some('"') => {
+                self.bump();
+                let mut value = String::new();
+                loop {
+                    match self.bump() {
+                        Some('"') => return Ok(Expr::Str(value)),
+                        Some('\\') => match self.bump() {
+                            Some('u') => {
+                                let hex = self.rest().get(..4).ok_or("Invalid escape in Fluent string")?;
+                                let c = u32::from_str_radix(hex, 16)
+                                    .ok()
+                                    .and_then(char::from_u32)
+                                    .ok_or("Invalid escape in Fluent string")?;
+                                self.pos += 4;
+                                value.push(c);
+                            }
+                            Some(c) => value.push(c),
+                            None => break,
+                        },
+                        Some(c) => value.push(c),
+                        None => break,
+                    }
+                }
+                Err("Unterminated string literal in Fluent pattern".to_string())
+            }
+            Some('{') => {
+                self.bump();
+                let expr = self.expression()?;
+                self.skip_blank();
+                self.expect("}")?;
+                Ok(expr)
+            }
+            Some('$') => {
+                self.bump();
+                Ok(Expr::Var(self.identifier()?))
+            }
+            Some('-') if !self.rest()[1..].starts_with(|c: char| c.is_ascii_digit()) => {
+                self.bump();
+                let id = self.identifier()?;
+                let attr = self.attribute()?;
+                let args = if self.peek() == Some('(') {
+                    self.call_arguments()?.1
+                } else {
+                    Vec::new()
+                };
+                Ok(Expr::Term { id, attr, args })
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                self.bump();
+                while matches!(self.peek(), Some(c) if c.is_ascii_digit() || c == '.') {
+                    self.bump();
+                }
+                let raw = &self.src[start..self.pos];
+                let value = raw.parse().map_err(|_| format!("Invalid number '{}' in Fluent pattern", raw))?;
+                Ok(Expr::Num(value, raw.to_string()))
+            }
+            Some(c) if c.is_ascii_alphabetic() => {
+                let id = self.identifier()?;
+                if self.peek() == Some('(') {
+                    let (args, _) = self.call_arguments()?;
+                    return Ok(Expr::Call { name: id, args });
+                }
+                let attr = self.attribute()?;
+                Ok(Expr::Message { id, attr })
+            }
+            _ => Err(format!("Invalid expression in Fluent pattern at '{}'", self.rest())),
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, String> {
+        let len = self
+            .rest()
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(self.rest().len());
+        let id = &self.rest()[..len];
+        if !is_identifier(id) {
+            return Err(format!("Invalid identifier in Fluent pattern at '{}'", self.rest()));
+        }
+        self.pos += len;
+        Ok(id.to_string())
+    }
+
+    fn attribute(&mut self) -> Result<Option<String>, String> {
+        if self.eat(".") {
+            Ok(Some(self.identifier()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Parses `(positional, name: value, ...)`.
+    fn call_arguments(&mut self) -> Result<CallArguments, String> {
+        self.expect("(")?;
+        let mut positional = Vec::new();
+        let mut named = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.eat(")") {
+                return Ok((positional, named));
+            }
+            let expr = self.inline_expression()?;
+            self.skip_blank();
+            match expr {
+                Expr::Message { id, attr: None } if self.eat(":") => {
+                    self.skip_blank();
+                    named.push((id, self.inline_expression()?));
+                }
+                expr => positional.push(expr),
+            }
+            self.skip_blank();
+            if !self.eat(",") {
+                self.skip_blank();
+                self.expect(")")?;
+                return Ok((positional, named));
+            }
+        }
+    }
+}
+
+enum Value {
+    Str(String),
+    Num(f64, String),
+}
+
+impl Value {
+    fn into_string(self) -> String {
+        match self {
+            Value::Str(s) | Value::Num(_, s) => s,
+        }
+    }
+}
+
+/// Evaluates Fluent patterns against a locale's translations and the call args.
+pub struct Scope<'a> {
+    pub locale: &'a str,
+    pub map: &'a HashMap<String, TranslationValue>,
+    pub args: &'a HashMap<String, FormatArg>,
+}
+
+impl Scope<'_> {
+    /// Formats a stored message value (a pattern, or a map with a `_` value).
+    pub fn format_value(&self, value: &TranslationValue) -> Result<String, String> {
+        self.format_value_at(value, self.args, 0)
+    }
+
+    fn format_value_at(&self, value: &TranslationValue, args: &HashMap<String, FormatArg>, depth: usize) -> Result<String, String> {
+        let pattern = match value {
+            TranslationValue::String(s) => s,
+            TranslationValue::Nested(map) => match map.get(VALUE_KEY) {
+                Some(TranslationValue::String(s)) => s,
+                _ => return Err("Fluent message has no value".to_string()),
+            },
+        };
+        if depth > MAX_DEPTH {
+            return Err("Fluent references are nested too deeply".to_string());
+        }
+
+        let nodes = parse_pattern(pattern)?;
+        self.format_nodes(&nodes, args, depth)
+    }
+
+    fn format_nodes(&self, nodes: &[Node], args: &HashMap<String, FormatArg>, depth: usize) -> Result<String, String> {
+        let mut out = String::new();
+        for node in nodes {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Placeable(expr) => out.push_str(&self.eval(expr, args, depth)?.into_string()),
+            }
+        }
+        Ok(out)
+    }
+
+    fn eval(&self, expr: &Expr, args: &HashMap<String, FormatArg>, depth: usize) -> Result<Value, String> {
+        Ok(match expr {
+            Expr::Str(s) => Value::Str(s.clone()),
+            Expr::Num(n, raw) => Value::Num(*n, raw.clone()),
+            Expr::Var(name) => match args.get(name) {
+                Some(FormatArg::Number(n)) => Value::Num(*n, FormatArg::Number(*n).to_string()),
+                Some(arg) => Value::Str(arg.to_string()),
+                None => Value::Str(format!("{{${}}}", name)),
+            },
+            Expr::Message { id, attr } => {
+                let key = reference_key(id, attr);
+                match get_value_by_key(self.map, &key) {
+                    Ok(value) => Value::Str(self.format_value_at(value, args, depth + 1)?),
+                    Err(_) => Value::Str(format!("{{{}}}", key)),
+                }
+            }
+            Expr::Term { id, attr, args: term_args } => {
+                let key = reference_key(&format!("-{}", id), attr);
+                // Terms only see the arguments passed to them explicitly.
+                let mut scoped = HashMap::new();
+                for (name, arg) in term_args {
+                    let value = match self.eval(arg, args, depth)? {
+                        Value::Num(n, _) => FormatArg::Number(n),
+                        Value::Str(s) => FormatArg::String(s),
+                    };
+                    scoped.insert(name.clone(), value);
+                }
+                match get_value_by_key(self.map, &key) {
+                    Ok(value) => Value::Str(self.format_value_at(value, &scoped, depth + 1)?),
+                    Err(_) => Value::Str(format!("{{{}}}", key)),
+                }
+            }
+            // Formatting options are left to the host; `NUMBER` only makes its
+            // argument numeric so it can drive plural selection.
+            Expr::Call { name, args: call_args } => match (call_args.first(), name.as_str()) {
+                (Some(arg), "NUMBER") => match self.eval(arg, args, depth)? {
+                    Value::Str(s) => match s.parse() {
+                        Ok(n) => Value::Num(n, s),
+                        Err(_) => Value::Str(s),
+                    },
+                    value => value,
+                },
+                (Some(arg), _) => self.eval(arg, args, depth)?,
+                (None, _) => Value::Str(String::new()),
+            },
+            Expr::Select { selector, variants } => {
+                let selected = self.eval(selector, args, depth)?;
+                let variant = self.select(&selected, variants);
+                Value::Str(self.format_nodes(&variant.pattern, args, depth)?)
+            }
+        })
+    }
+
+    fn select<'v>(&self, selected: &Value, variants: &'v [Variant]) -> &'v Variant {
+        let exact = variants.iter().find(|variant| match selected {
+            Value::Str(s) => variant.key == *s,
+            Value::Num(n, _) => variant.key.parse::<f64>() == Ok(*n),
+        });
+        let category = match selected {
+            Value::Num(n, _) if exact.is_none() => {
+                let category = plural_category(self.locale, *n);
+                variants.iter().find(|variant| variant.key == category)
+            }
+            _ => None,
+        };
+
+        exact
+            .or(category)
+            .or_else(|| variants.iter().find(|variant| variant.default))
+            .expect("select expressions are parsed with a default variant")
+    }
+}
+
+fn reference_key(id: &str, attr: &Option<String>) -> String {
+    match attr {
+        Some(attr) => format!("{}.{}", id, attr),
+        None => id.to_string(),
+    }
+}
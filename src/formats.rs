@@ -0,0 +1,249 @@
+//! YAML, TOML and Java `.properties` parsing.
+//!
+//! Every parser produces the same `key → TranslationValue` structure that
+//! `setTranslations` accepts. Scalars (numbers, booleans, dates) become
+//! strings, sequences become maps keyed by index and YAML nulls are skipped.
+
+use std::collections::HashMap;
+use crate::helpers::update_translation_recursive;
+use crate::TranslationValue;
+
+/// A document format `loadTranslations` can read.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+    Properties,
+}
+
+impl Format {
+    /// Parses an explicit format name such as `"yaml"` or `"properties"`.
+    pub fn from_name(name: &str) -> Result<Format, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Ok(Format::Json),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            "toml" => Ok(Format::Toml),
+            "properties" => Ok(Format::Properties),
+            _ => Err(format!("Unsupported translations format '{}'", name)),
+        }
+    }
+
+    /// Picks a format from a response's `Content-Type`, falling back to the
+    /// URL's file extension and finally to JSON.
+    pub fn detect(content_type: Option<&str>, url: &str) -> Format {
+        let mime = content_type
+            .and_then(|ct| ct.split(';').next())
+            .map(|ct| ct.trim().to_ascii_lowercase())
+            .unwrap_or_default();
+        match mime.as_str() {
+            "application/yaml" | "application/x-yaml" | "text/yaml" | "text/x-yaml" => return Format::Yaml,
+            "application/toml" | "text/toml" | "text/x-toml" => return Format::Toml,
+            "text/x-java-properties" | "text/x-properties" => return Format::Properties,
+            "application/json" => return Format::Json,
+            _ => {}
+        }
+
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        let extension = path.rsplit_once('.').map(|(_, ext)| ext).unwrap_or_default();
+        Format::from_name(extension).unwrap_or(Format::Json)
+    }
+
+    pub fn parse(self, text: &str) -> Result<HashMap<String, TranslationValue>, String> {
+        match self {
+            Format::Json => serde_json::from_str(text).map_err(|e| format!("Invalid JSON: {}", e)),
+            Format::Yaml => parse_yaml(text),
+            Format::Toml => parse_toml(text),
+            Format::Properties => parse_properties(text),
+        }
+    }
+}
+
+pub fn parse_yaml(text: &str) -> Result<HashMap<String, TranslationValue>, String> {
+    let value: serde_yaml::Value = serde_yaml::from_str(text).map_err(|e| format!("Invalid YAML: {}", e))?;
+    match yaml_to_translation(value) {
+        Some(TranslationValue::Nested(map)) => Ok(map),
+        None => Ok(HashMap::new()),
+        Some(TranslationValue::String(_)) => Err("Invalid YAML: expected a mapping at the top level".to_string()),
+    }
+}
+
+fn yaml_to_translation(value: serde_yaml::Value) -> Option<TranslationValue> {
+    use serde_yaml::Value;
+
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(b) => TranslationValue::String(b.to_string()),
+        Value::Number(n) => TranslationValue::String(n.to_string()),
+        Value::String(s) => TranslationValue::String(s),
+        Value::Sequence(items) => TranslationValue::Nested(
+            items
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, item)| Some((i.to_string(), yaml_to_translation(item)?)))
+                .collect(),
+        ),
+        Value::Mapping(mapping) => TranslationValue::Nested(
+            mapping
+                .into_iter()
+                .filter_map(|(key, item)| {
+                    let key = match key {
+                        Value::String(s) => s,
+                        Value::Bool(b) => b.to_string(),
+                        Value::Number(n) => n.to_string(),
+                        _ => return None,
+                    };
+                    Some((key, yaml_to_translation(item)?))
+                })
+                .collect(),
+        ),
+        Value::Tagged(tagged) => return yaml_to_translation(tagged.value),
+    })
+}
+
+pub fn parse_toml(text: &str) -> Result<HashMap<String, TranslationValue>, String> {
+    let table: toml::Table = toml::from_str(text).map_err(|e| format!("Invalid TOML: {}", e))?;
+    Ok(table
+        .into_iter()
+        .map(|(key, value)| (key, toml_to_translation(value)))
+        .collect())
+}
+
+fn toml_to_translation(value: toml::Value) -> TranslationValue {
+    use toml::Value;
+
+    match value {
+        Value::String(s) => TranslationValue::String(s),
+        Value::Integer(i) => TranslationValue::String(i.to_string()),
+        Value::Float(f) => TranslationValue::String(f.to_string()),
+        Value::Boolean(b) => TranslationValue::String(b.to_string()),
+        Value::Datetime(d) => TranslationValue::String(d.to_string()),
+        Value::Array(items) => TranslationValue::Nested(
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| (i.to_string(), toml_to_translation(item)))
+                .collect(),
+        ),
+        Value::Table(table) => TranslationValue::Nested(
+            table
+                .into_iter()
+                .map(|(key, item)| (key, toml_to_translation(item)))
+                .collect(),
+        ),
+    }
+}
+
+/// Parses a Java `.properties` file. Dotted keys become nested maps.
+pub fn parse_properties(text: &str) -> Result<HashMap<String, TranslationValue>, String> {
+    let mut map = HashMap::new();
+    let mut lines = text.lines().enumerate();
+
+    while let Some((index, line)) = lines.next() {
+        let mut logical = line.trim_start().to_string();
+        if logical.is_empty() || logical.starts_with('#') || logical.starts_with('!') {
+            continue;
+        }
+        // A line ending in an odd number of backslashes continues on the next one.
+        while ends_with_continuation(&logical) {
+            logical.pop();
+            match lines.next() {
+                Some((_, next)) => logical.push_str(next.trim_start()),
+                None => break,
+            }
+        }
+
+        let (key, value) = split_property(&logical);
+        let key = unescape_property(key)?;
+        let value = unescape_property(value)?;
+        let keys: Vec<&str> = key.split('.').collect();
+        let error = |e: &str| format!("Invalid properties key '{}' on line {}: {}", key, index + 1, e);
+        if holds_subtree(&map, &keys) {
+            return Err(error("a nested key was already defined under it"));
+        }
+        update_translation_recursive(&mut map, &keys, TranslationValue::String(value)).map_err(|e| error(&e))?;
+    }
+    Ok(map)
+}
+
+/// Whether the value at `keys` is a nested map, which a string must not replace.
+fn holds_subtree(map: &HashMap<String, TranslationValue>, keys: &[&str]) -> bool {
+    let mut current = map;
+    for (i, key) in keys.iter().enumerate() {
+        match current.get(*key) {
+            Some(TranslationValue::Nested(child)) if i + 1 < keys.len() => current = child,
+            Some(TranslationValue::Nested(_)) => return true,
+            _ => return false,
+        }
+    }
+    false
+}
+
+fn ends_with_continuation(line: &str) -> bool {
+    line.chars().rev().take_while(|c| *c == '\\').count() % 2 == 1
+}
+
+/// Splits at the first unescaped `=`, `:` or whitespace.
+fn split_property(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '=' | ':' => return (&line[..i], line[i + 1..].trim_start()),
+            c if c.is_whitespace() => {
+                let rest = line[i..].trim_start();
+                let rest = rest
+                    .strip_prefix(['=', ':'])
+                    .map(str::trim_start)
+                    .unwrap_or(rest);
+                return (&line[..i], rest);
+            }
+            _ => {}
+        }
+    }
+    (line, "")
+}
+
+fn unescape_property(s: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    let mut pending_surrogate: Option<u32> = None;
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        let escaped = match chars.next() {
+            Some(escaped) => escaped,
+            None => break,
+        };
+        match escaped {
+            't' => out.push('\t'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            'f' => out.push('\u{c}'),
+            'u' => {
+                let hex: String = chars.by_ref().take(4).collect();
+                let unit = u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == 4)
+                    .ok_or_else(|| format!("Invalid unicode escape '\\u{}'", hex))?;
+                match (pending_surrogate.take(), unit) {
+                    (None, 0xD800..=0xDBFF) => pending_surrogate = Some(unit),
+                    (Some(high), 0xDC00..=0xDFFF) => {
+                        let code = 0x10000 + ((high - 0xD800) << 10) + (unit - 0xDC00);
+                        out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    (_, unit) => out.push(char::from_u32(unit).unwrap_or(char::REPLACEMENT_CHARACTER)),
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    Ok(out)
+}
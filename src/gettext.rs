@@ -0,0 +1,679 @@
+//! Gettext `.po` / `.mo` import and `.po` export.
+//!
+//! A message's `msgid` is used literally as a top-level key, so ids such as
+//! `Loading...` or `File.Open` are never split into a path. `msgctxt` is folded
+//! into the key with the context separator (`open#verb`) and kept as the key's
+//! context metadata. Plural messages become a map keyed by CLDR category,
+//! mapped from the form indexes through the `Plural-Forms` header, and keep
+//! their `msgid_plural` in metadata. Extracted comments (`#.`) carry the key's
+//! description.
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::{TryFrom, TryInto};
+use serde::Serialize;
+use serde_json::Value;
+use crate::helpers::{context_key, escape_segment, has_plural_rules, is_plural_map, join_key, plural_category};
+use crate::metadata::KeyMetadata;
+use crate::TranslationValue;
+
+/// The `Plural-Forms` gettext assumes when a file does not declare one.
+pub const DEFAULT_PLURAL_FORMS: &str = "nplurals=2; plural=(n != 1);";
+
+/// Metadata field holding a plural message's `msgid_plural`.
+const MSGID_PLURAL: &str = "msgidPlural";
+
+#[derive(Default, Debug)]
+pub struct PoEntry {
+    pub context: Option<String>,
+    pub id: String,
+    pub id_plural: Option<String>,
+    pub strs: Vec<String>,
+    pub fuzzy: bool,
+    pub comments: Vec<String>,
+}
+
+impl PoEntry {
+    pub fn is_header(&self) -> bool {
+        self.id.is_empty() && self.context.is_none()
+    }
+
+    pub fn is_translated(&self) -> bool {
+        self.strs.iter().any(|s| !s.is_empty())
+    }
+
+    /// The top-level key this entry is stored under, taken literally.
+    pub fn key(&self) -> String {
+        match &self.context {
+            Some(context) => context_key(&self.id, context),
+            None => self.id.clone(),
+        }
+    }
+
+    /// The entry's text, or for a plural entry a map from the CLDR categories
+    /// of each form index (see [`PluralForms::categories`]) to its form.
+    pub fn value(&self, categories: &[Vec<String>]) -> TranslationValue {
+        if self.id_plural.is_none() {
+            return TranslationValue::String(self.strs.first().cloned().unwrap_or_default());
+        }
+        let mut forms = HashMap::new();
+        for (text, categories) in self.strs.iter().zip(categories) {
+            for category in categories {
+                forms.insert(category.clone(), TranslationValue::String(text.clone()));
+            }
+        }
+        TranslationValue::Nested(forms)
+    }
+
+    pub fn metadata(&self) -> KeyMetadata {
+        let mut metadata = KeyMetadata {
+            description: Some(self.comments.join("\n")).filter(|comments| !comments.is_empty()),
+            context: self.context.clone(),
+            ..KeyMetadata::default()
+        };
+        if let Some(id_plural) = &self.id_plural {
+            metadata.extra.insert(MSGID_PLURAL.to_string(), Value::from(id_plural.as_str()));
+        }
+        metadata
+    }
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub imported: usize,
+    pub skipped_fuzzy: usize,
+    pub plural_forms: Option<String>,
+}
+
+/// Reads a header field such as `Plural-Forms` from the header entry's msgstr.
+pub fn header_field(entries: &[PoEntry], name: &str) -> Option<String> {
+    let header = entries.iter().find(|e| e.is_header())?;
+    header.strs.first()?.lines().find_map(|line| {
+        let (field, value) = line.split_once(':')?;
+        if field.trim().eq_ignore_ascii_case(name) {
+            Some(value.trim().to_string())
+        } else {
+            None
+        }
+    })
+}
+
+/// A parsed `Plural-Forms` header: the number of forms and the C expression
+/// that picks the form index for `n`.
+pub struct PluralForms {
+    count: usize,
+    plural: Expr,
+}
+
+impl PluralForms {
+    pub fn parse(header: &str) -> Result<PluralForms, String> {
+        let error = |msg: &str| format!("Invalid Plural-Forms '{}': {}", header, msg);
+        let mut count = None;
+        let mut plural = None;
+        for part in header.split(';') {
+            match part.split_once('=') {
+                Some((name, value)) if name.trim() == "nplurals" => {
+                    count = Some(value.trim().parse::<usize>().map_err(|_| error("nplurals is not a number"))?);
+                }
+                Some((name, value)) if name.trim() == "plural" => {
+                    plural = Some(ExprParser::parse(value).map_err(|e| error(&e))?);
+                }
+                _ => {}
+            }
+        }
+        match (count, plural) {
+            (Some(count), Some(plural)) if count > 0 => Ok(PluralForms { count, plural }),
+            _ => Err(error("expected nplurals and plural")),
+        }
+    }
+
+    /// The CLDR categories of each form index for `locale`: from its plural
+    /// rules when the host knows them, otherwise by form index.
+    pub fn locale_categories(&self, locale: &str) -> Result<Vec<Vec<String>>, String> {
+        if has_plural_rules(locale) {
+            self.categories(|n| plural_category(locale, n))
+        } else {
+            self.index_categories()
+        }
+    }
+
+    /// Assigns categories by form index, the last form being `other`: two
+    /// forms are `one` and `other`, three add `few`, four `many`, five `two`
+    /// and six `zero`.
+    pub fn index_categories(&self) -> Result<Vec<Vec<String>>, String> {
+        let categories: &[&str] = match self.count {
+            1 => &["other"],
+            2 => &["one", "other"],
+            3 => &["one", "few", "other"],
+            4 => &["one", "few", "many", "other"],
+            5 => &["one", "two", "few", "many", "other"],
+            6 => &["zero", "one", "two", "few", "many", "other"],
+            count => return Err(format!("Plural-Forms has {} forms, more than the 6 plural categories", count)),
+        };
+        Ok(categories.iter().map(|category| vec![category.to_string()]).collect())
+    }
+
+    /// The CLDR categories of each form index, found by running the integers
+    /// 0 to 1000 through both the expression and `category`, the locale's
+    /// plural rules. A form takes the category most of its integers have;
+    /// the last form also stands for `other` when no form has it.
+    pub fn categories(&self, category: impl Fn(f64) -> String) -> Result<Vec<Vec<String>>, String> {
+        let mut counts: Vec<HashMap<String, usize>> = vec![HashMap::new(); self.count];
+        for n in 0..=1000u64 {
+            if let Some(counts) = usize::try_from(self.plural.eval(n)).ok().and_then(|i| counts.get_mut(i)) {
+                *counts.entry(category(n as f64)).or_default() += 1;
+            }
+        }
+
+        let mut categories: Vec<Vec<String>> = Vec::with_capacity(self.count);
+        for counts in counts {
+            let best = counts.into_iter().max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0))).map(|(category, _)| category);
+            if let Some(category) = &best {
+                if categories.iter().flatten().any(|existing| existing == category) {
+                    return Err(format!("Plural-Forms has more than one form for the '{}' category", category));
+                }
+            }
+            categories.push(best.into_iter().collect());
+        }
+        if !categories.iter().flatten().any(|category| category == "other") {
+            if let Some(last) = categories.iter_mut().rev().find(|categories| !categories.is_empty()) {
+                last.push("other".to_string());
+            }
+        }
+        Ok(categories)
+    }
+}
+
+#[derive(Debug)]
+enum Expr {
+    N,
+    Number(u64),
+    Not(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinaryOp {
+    /// Binding power; higher binds tighter, as in C.
+    fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Eq | BinaryOp::Ne => 3,
+            BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => 4,
+            BinaryOp::Add | BinaryOp::Sub => 5,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => 6,
+        }
+    }
+}
+
+impl Expr {
+    fn eval(&self, n: u64) -> u64 {
+        match self {
+            Expr::N => n,
+            Expr::Number(value) => *value,
+            Expr::Not(inner) => (inner.eval(n) == 0) as u64,
+            Expr::Conditional(condition, then, otherwise) => {
+                if condition.eval(n) != 0 {
+                    then.eval(n)
+                } else {
+                    otherwise.eval(n)
+                }
+            }
+            Expr::Binary(op, left, right) => {
+                let (a, b) = (left.eval(n), right.eval(n));
+                match op {
+                    BinaryOp::Or => (a != 0 || b != 0) as u64,
+                    BinaryOp::And => (a != 0 && b != 0) as u64,
+                    BinaryOp::Eq => (a == b) as u64,
+                    BinaryOp::Ne => (a != b) as u64,
+                    BinaryOp::Lt => (a < b) as u64,
+                    BinaryOp::Le => (a <= b) as u64,
+                    BinaryOp::Gt => (a > b) as u64,
+                    BinaryOp::Ge => (a >= b) as u64,
+                    BinaryOp::Add => a.wrapping_add(b),
+                    BinaryOp::Sub => a.wrapping_sub(b),
+                    BinaryOp::Mul => a.wrapping_mul(b),
+                    BinaryOp::Div => a.checked_div(b).unwrap_or(0),
+                    BinaryOp::Rem => a.checked_rem(b).unwrap_or(0),
+                }
+            }
+        }
+    }
+}
+
+/// Parser for the C subset used by `plural=` expressions.
+struct ExprParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> ExprParser<'a> {
+    fn parse(input: &'a str) -> Result<Expr, String> {
+        let mut parser = ExprParser { input, pos: 0 };
+        let expr = parser.conditional()?;
+        parser.skip_whitespace();
+        if parser.pos != input.len() {
+            return Err(format!("unexpected '{}'", &input[parser.pos..]));
+        }
+        Ok(expr)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        if self.input[self.pos..].starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn conditional(&mut self) -> Result<Expr, String> {
+        let condition = self.binary(1)?;
+        if !self.eat("?") {
+            return Ok(condition);
+        }
+        let then = self.conditional()?;
+        if !self.eat(":") {
+            return Err("expected ':'".to_string());
+        }
+        let otherwise = self.conditional()?;
+        Ok(Expr::Conditional(Box::new(condition), Box::new(then), Box::new(otherwise)))
+    }
+
+    fn binary(&mut self, min_precedence: u8) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while let Some(op) = self.peek_op().filter(|op| op.precedence() >= min_precedence) {
+            self.operator(op);
+            let right = self.binary(op.precedence() + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn peek_op(&mut self) -> Option<BinaryOp> {
+        self.skip_whitespace();
+        let rest = &self.input[self.pos..];
+        let op = match rest.as_bytes() {
+            [b'|', b'|', ..] => BinaryOp::Or,
+            [b'&', b'&', ..] => BinaryOp::And,
+            [b'=', b'=', ..] => BinaryOp::Eq,
+            [b'!', b'=', ..] => BinaryOp::Ne,
+            [b'<', b'=', ..] => BinaryOp::Le,
+            [b'>', b'=', ..] => BinaryOp::Ge,
+            [b'<', ..] => BinaryOp::Lt,
+            [b'>', ..] => BinaryOp::Gt,
+            [b'+', ..] => BinaryOp::Add,
+            [b'-', ..] => BinaryOp::Sub,
+            [b'*', ..] => BinaryOp::Mul,
+            [b'/', ..] => BinaryOp::Div,
+            [b'%', ..] => BinaryOp::Rem,
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    fn operator(&mut self, op: BinaryOp) {
+        self.pos += match op {
+            BinaryOp::Or | BinaryOp::And | BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Le | BinaryOp::Ge => 2,
+            _ => 1,
+        };
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let inner = self.conditional()?;
+            if !self.eat(")") {
+                return Err("expected ')'".to_string());
+            }
+            return Ok(inner);
+        }
+        if self.eat("n") {
+            return Ok(Expr::N);
+        }
+        let rest = &self.input[self.pos..];
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return Err(match rest.chars().next() {
+                Some(c) => format!("unexpected '{}'", c),
+                None => "unexpected end of expression".to_string(),
+            });
+        }
+        self.pos += digits;
+        rest[..digits].parse().map(Expr::Number).map_err(|_| "number out of range".to_string())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+    Context,
+    Id,
+    IdPlural,
+    Str(usize),
+}
+
+pub fn parse_po(text: &str) -> Result<Vec<PoEntry>, String> {
+    let mut entries = Vec::new();
+    let mut current = PoEntry::default();
+    let mut has_id = false;
+    let mut field: Option<Field> = None;
+    let mut fuzzy = false;
+    let mut comments = Vec::new();
+
+    for (number, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        let error = |msg: &str| format!("PO parse error on line {}: {}", number + 1, msg);
+
+        if line.is_empty() || line.starts_with('#') {
+            if has_id {
+                entries.push(std::mem::take(&mut current));
+                has_id = false;
+                field = None;
+            }
+            // Obsolete (`#~`) entries are dropped; flags apply to the next entry.
+            if let Some(flags) = line.strip_prefix("#,") {
+                fuzzy |= flags.split(',').any(|flag| flag.trim() == "fuzzy");
+            }
+            if let Some(comment) = line.strip_prefix("#.") {
+                comments.push(comment.trim().to_string());
+            }
+            continue;
+        }
+
+        if line.starts_with('"') {
+            let target = field.ok_or_else(|| error("string continuation without a keyword"))?;
+            let value = unquote(line).ok_or_else(|| error("malformed string"))?;
+            field_mut(&mut current, target).push_str(&value);
+            continue;
+        }
+
+        let (keyword, rest) = line
+            .split_once(|c: char| c.is_whitespace())
+            .ok_or_else(|| error("expected a keyword followed by a string"))?;
+        let value = unquote(rest.trim()).ok_or_else(|| error("malformed string"))?;
+
+        let next = match keyword {
+            "msgctxt" => Field::Context,
+            "msgid" => Field::Id,
+            "msgid_plural" => Field::IdPlural,
+            "msgstr" => Field::Str(0),
+            _ => {
+                let index = keyword
+                    .strip_prefix("msgstr[")
+                    .and_then(|rest| rest.strip_suffix(']'))
+                    .and_then(|index| index.parse().ok())
+                    .ok_or_else(|| error(&format!("unknown keyword '{}'", keyword)))?;
+                Field::Str(index)
+            }
+        };
+
+        // A new msgctxt/msgid after a msgstr starts the next entry.
+        if matches!(next, Field::Context | Field::Id) && !current.strs.is_empty() {
+            entries.push(std::mem::take(&mut current));
+            has_id = false;
+        }
+        if matches!(next, Field::Context | Field::Id) {
+            current.fuzzy = std::mem::take(&mut fuzzy) || current.fuzzy;
+            current.comments.append(&mut comments);
+        }
+        if let Field::Id = next {
+            has_id = true;
+        }
+
+        match next {
+            Field::Context => current.context = Some(String::new()),
+            Field::IdPlural => current.id_plural = Some(String::new()),
+            Field::Str(index) => {
+                if current.strs.len() <= index {
+                    current.strs.resize(index + 1, String::new());
+                }
+            }
+            Field::Id => {}
+        }
+        field_mut(&mut current, next).push_str(&value);
+        field = Some(next);
+    }
+
+    if has_id {
+        entries.push(current);
+    }
+    Ok(entries)
+}
+
+fn field_mut(entry: &mut PoEntry, field: Field) -> &mut String {
+    match field {
+        Field::Context => entry.context.get_or_insert_with(String::new),
+        Field::Id => &mut entry.id,
+        Field::IdPlural => entry.id_plural.get_or_insert_with(String::new),
+        Field::Str(index) => &mut entry.strs[index],
+    }
+}
+
+fn unquote(s: &str) -> Option<String> {
+    let inner = s.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next()? {
+            'n' => out.push('\n'),
+            't' => out.push('\t'),
+            'r' => out.push('\r'),
+            'a' => out.push('\u{7}'),
+            'b' => out.push('\u{8}'),
+            'f' => out.push('\u{c}'),
+            'v' => out.push('\u{b}'),
+            other => out.push(other),
+        }
+    }
+    Some(out)
+}
+
+pub fn parse_mo(bytes: &[u8]) -> Result<Vec<PoEntry>, String> {
+    let word = |offset: usize, big_endian: bool| -> Result<usize, String> {
+        let raw: [u8; 4] = bytes
+            .get(offset..offset.saturating_add(4))
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| "Invalid MO file: unexpected end of data".to_string())?;
+        Ok(if big_endian { u32::from_be_bytes(raw) } else { u32::from_le_bytes(raw) } as usize)
+    };
+
+    let big_endian = match word(0, false)? {
+        0x9504_12de => false,
+        0xde12_0495 => true,
+        _ => return Err("Invalid MO file: bad magic".to_string()),
+    };
+    let count = word(8, big_endian)?;
+    let originals = word(12, big_endian)?;
+    let translations = word(16, big_endian)?;
+
+    let string_at = |table: usize, i: usize| -> Result<&str, String> {
+        let descriptor = table.saturating_add(i.saturating_mul(8));
+        let len = word(descriptor, big_endian)?;
+        let offset = word(descriptor.saturating_add(4), big_endian)?;
+        let raw = offset
+            .checked_add(len)
+            .and_then(|end| bytes.get(offset..end))
+            .ok_or_else(|| "Invalid MO file: string out of range".to_string())?;
+        std::str::from_utf8(raw).map_err(|_| "Invalid MO file: string is not UTF-8".to_string())
+    };
+
+    let mut entries = Vec::with_capacity(count.min(bytes.len()));
+    for i in 0..count {
+        let original = string_at(originals, i)?;
+        let translation = string_at(translations, i)?;
+
+        let (context, original) = match original.split_once('\u{4}') {
+            Some((context, original)) => (Some(context.to_string()), original),
+            None => (None, original),
+        };
+        let mut ids = original.split('\0');
+        entries.push(PoEntry {
+            context,
+            id: ids.next().unwrap_or_default().to_string(),
+            id_plural: ids.next().map(str::to_string),
+            strs: translation.split('\0').map(str::to_string).collect(),
+            fuzzy: false,
+            comments: Vec::new(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Writes a locale's translation tree as a `.po` file, with key descriptions
+/// from `metadata` as extracted comments. Plural maps are written in the form
+/// order of `plural_forms`, whose per-index `categories` come from
+/// [`PluralForms::categories`].
+pub fn write_po(
+    locale: &str,
+    map: &HashMap<String, TranslationValue>,
+    metadata: Option<&HashMap<String, KeyMetadata>>,
+    plural_forms: &str,
+    categories: &[Vec<String>],
+) -> String {
+    let mut out = String::from("msgid \"\"\nmsgstr \"\"\n");
+    out.push_str(&format!("\"Language: {}\\n\"\n", escape(locale)));
+    out.push_str("\"MIME-Version: 1.0\\n\"\n");
+    out.push_str("\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+    out.push_str("\"Content-Transfer-Encoding: 8bit\\n\"\n");
+    out.push_str(&format!("\"Plural-Forms: {}\\n\"\n", escape(plural_forms)));
+
+    let mut messages = BTreeMap::new();
+    for (key, value) in map {
+        collect_messages(&escape_segment(key), key, value, categories, &mut messages);
+    }
+
+    for (path, (key, strs)) in messages {
+        let meta = metadata.and_then(|metadata| metadata.get(&path));
+        let (id, context) = match meta.and_then(|meta| meta.context.as_deref()) {
+            Some(context) if key.ends_with(&context_key("", context)) => {
+                (&key[..key.len() - context.len() - 1], Some(context))
+            }
+            // Only a recorded msgctxt splits the key; a `#` in a msgid is text.
+            _ => (&key[..], None),
+        };
+        out.push('\n');
+        if let Some(description) = meta.and_then(|meta| meta.description.as_deref()) {
+            for line in description.lines() {
+                out.push_str(&format!("#. {}\n", line));
+            }
+        }
+        if let Some(context) = context {
+            out.push_str(&format!("msgctxt \"{}\"\n", escape(context)));
+        }
+        out.push_str(&format!("msgid \"{}\"\n", escape(id)));
+        match strs {
+            Message::Singular(text) => out.push_str(&format!("msgstr \"{}\"\n", escape(text))),
+            Message::Plural(forms) => {
+                let id_plural = meta.and_then(|meta| meta.extra.get(MSGID_PLURAL)?.as_str()).unwrap_or(id);
+                out.push_str(&format!("msgid_plural \"{}\"\n", escape(id_plural)));
+                for (i, text) in forms.iter().enumerate() {
+                    out.push_str(&format!("msgstr[{}] \"{}\"\n", i, escape(text)));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Whether `map` holds a plural map at any depth.
+pub fn contains_plural_map(map: &HashMap<String, TranslationValue>) -> bool {
+    map.values().any(|value| match value {
+        TranslationValue::Nested(map) => is_plural_map(map) || contains_plural_map(map),
+        TranslationValue::String(_) => false,
+    })
+}
+
+enum Message<'a> {
+    Singular(&'a str),
+    Plural(Vec<&'a str>),
+}
+
+/// Collects the messages under `value`, keyed by dotted path. `id` is the
+/// msgid: the literal top-level key, with nested keys joined by `.`.
+fn collect_messages<'a>(
+    path: &str,
+    id: &str,
+    value: &'a TranslationValue,
+    categories: &[Vec<String>],
+    out: &mut BTreeMap<String, (String, Message<'a>)>,
+) {
+    match value {
+        TranslationValue::String(text) => {
+            out.insert(path.to_string(), (id.to_string(), Message::Singular(text)));
+        }
+        TranslationValue::Nested(map) => {
+            if let Some(forms) = plural_forms(map, categories) {
+                out.insert(path.to_string(), (id.to_string(), Message::Plural(forms)));
+                return;
+            }
+            for (key, child) in map {
+                collect_messages(&join_key(path, key), &format!("{}.{}", id, key), child, categories, out);
+            }
+        }
+    }
+}
+
+/// Returns the forms of a plural map in form index order: a map keyed by CLDR
+/// category, falling back to `other` for a category it lacks, or a
+/// `{ "0": ..., "1": ... }` map of form indexes.
+fn plural_forms<'a>(map: &'a HashMap<String, TranslationValue>, categories: &[Vec<String>]) -> Option<Vec<&'a str>> {
+    let text = |key: &str| match map.get(key) {
+        Some(TranslationValue::String(text)) => Some(text.as_str()),
+        _ => None,
+    };
+    if is_plural_map(map) {
+        return categories
+            .iter()
+            .map(|categories| categories.first().and_then(|category| text(category)).or_else(|| text("other")))
+            .collect();
+    }
+    if map.is_empty() {
+        return None;
+    }
+    (0..map.len()).map(|i| text(&i.to_string())).collect()
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
@@ -1,44 +1,245 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
+use serde::Deserialize;
+use wasm_bindgen::prelude::wasm_bindgen;
 use wasm_bindgen::JsValue;
 use crate::TranslationValue;
 
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = Intl, js_name = PluralRules)]
+    type PluralRules;
+
+    #[wasm_bindgen(constructor, js_namespace = Intl, js_class = "PluralRules", catch)]
+    fn new(locale: &str) -> Result<PluralRules, JsValue>;
+
+    #[wasm_bindgen(constructor, js_namespace = Intl, js_class = "PluralRules", catch)]
+    fn new_with_options(locale: &str, options: &JsValue) -> Result<PluralRules, JsValue>;
+
+    #[wasm_bindgen(method, js_class = "PluralRules")]
+    fn select(this: &PluralRules, n: f64) -> String;
+}
+
+/// A formatting argument as passed from JS: a string, a number or a boolean.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum FormatArg {
+    Number(f64),
+    Bool(bool),
+    String(String),
+}
+
+impl fmt::Display for FormatArg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            // Match JS number formatting: `5`, not `5.0`.
+            FormatArg::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            FormatArg::Number(n) => write!(f, "{}", n),
+            FormatArg::Bool(b) => write!(f, "{}", b),
+            FormatArg::String(s) => f.write_str(s),
+        }
+    }
+}
+
+thread_local! {
+    /// `Intl.PluralRules` per `(locale, ordinal)`, built on first use. `None`
+    /// records a locale the host does not recognise.
+    static PLURAL_RULES: RefCell<HashMap<(String, bool), Option<PluralRules>>> = RefCell::new(HashMap::new());
+}
+
+/// Turns a POSIX locale name such as `pt_BR.UTF-8@euro` into the BCP 47
+/// tag `Intl` accepts (`pt-BR`).
+fn bcp47_tag(locale: &str) -> String {
+    locale.split(['.', '@']).next().unwrap_or(locale).replace('_', "-")
+}
+
+/// Runs `f` with the cached rules for `locale`, or `None` when the locale is
+/// not recognised.
+fn with_plural_rules<R>(locale: &str, ordinal: bool, f: impl FnOnce(Option<&PluralRules>) -> R) -> R {
+    PLURAL_RULES.with(|rules| {
+        let mut rules = rules.borrow_mut();
+        let rules = rules.entry((locale.to_string(), ordinal)).or_insert_with(|| {
+            let tag = bcp47_tag(locale);
+            if !ordinal {
+                return PluralRules::new(&tag).ok();
+            }
+            let options = js_sys::Object::new();
+            let _ = js_sys::Reflect::set(&options, &JsValue::from("type"), &JsValue::from("ordinal"));
+            PluralRules::new_with_options(&tag, &options).ok()
+        });
+        f(rules.as_ref())
+    })
+}
+
+/// Selects the category of `n` with the cached rules for `locale`, falling
+/// back to `other` when the locale is not recognised.
+fn select_category(locale: &str, ordinal: bool, n: f64) -> String {
+    with_plural_rules(locale, ordinal, |rules| match rules {
+        Some(rules) => rules.select(n),
+        None => "other".to_string(),
+    })
+}
+
+/// Whether the host has plural rules for `locale`.
+pub fn has_plural_rules(locale: &str) -> bool {
+    with_plural_rules(locale, false, |rules| rules.is_some())
+}
+
+/// Returns the CLDR plural category (`one`, `few`, `other`, ...) of `n` for
+/// `locale`, falling back to `other` when the locale is not recognised.
+pub fn plural_category(locale: &str, n: f64) -> String {
+    select_category(locale, false, n)
+}
+
+/// Returns the CLDR ordinal category of `n` (`one` for "1st", `two` for "2nd"
+/// in English), falling back to `other`.
+pub fn ordinal_category(locale: &str, n: f64) -> String {
+    select_category(locale, true, n)
+}
+
+/// The CLDR plural categories, in their conventional order.
+pub const PLURAL_CATEGORIES: [&str; 6] = ["zero", "one", "two", "few", "many", "other"];
+
+/// Whether `map` holds plural forms: only CLDR category keys, including `other`.
+pub fn is_plural_map(map: &HashMap<String, TranslationValue>) -> bool {
+    map.contains_key("other") && map.keys().all(|key| PLURAL_CATEGORIES.contains(&key.as_str()))
+}
+
+/// Splits a dotted path into its raw key segments. A segment that itself
+/// contains `.` or `\` is written escaped (`a\.b`, see [`escape_segment`]);
+/// any other backslash is taken literally.
+pub fn key_segments(key: &str) -> KeySegments<'_> {
+    KeySegments { rest: Some(key) }
+}
+
+pub fn split_key(key: &str) -> Vec<Cow<'_, str>> {
+    key_segments(key).collect()
+}
+
+pub struct KeySegments<'a> {
+    rest: Option<&'a str>,
+}
+
+impl<'a> Iterator for KeySegments<'a> {
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Cow<'a, str>> {
+        let rest = self.rest?;
+        let bytes = rest.as_bytes();
+        let mut escaped = false;
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' if matches!(bytes.get(i + 1), Some(b'.' | b'\\')) => {
+                    escaped = true;
+                    i += 2;
+                }
+                b'.' => break,
+                _ => i += 1,
+            }
+        }
+        let segment = &rest[..i];
+        self.rest = rest.get(i + 1..);
+        if !escaped {
+            return Some(Cow::Borrowed(segment));
+        }
+        let mut out = String::with_capacity(segment.len());
+        let mut chars = segment.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('\\', Some(&next)) if next == '.' || next == '\\' => {
+                    out.push(next);
+                    chars.next();
+                }
+                _ => out.push(c),
+            }
+        }
+        Some(Cow::Owned(out))
+    }
+}
+
+/// Escapes a raw key so it is read back as a single segment of a dotted path:
+/// `{ "a.b": "x" }` lives at `a\.b`, `{ "a": { "b": "y" } }` at `a.b`.
+pub fn escape_segment(segment: &str) -> Cow<'_, str> {
+    if !segment.contains(['.', '\\']) {
+        return Cow::Borrowed(segment);
+    }
+    let mut out = String::with_capacity(segment.len() + 2);
+    for c in segment.chars() {
+        if c == '.' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    Cow::Owned(out)
+}
+
+/// The path of the raw key `segment` below the path `prefix`; an empty
+/// prefix is the locale root.
+pub fn join_key(prefix: &str, segment: &str) -> String {
+    if prefix.is_empty() {
+        escape_segment(segment).into_owned()
+    } else {
+        format!("{}.{}", prefix, escape_segment(segment))
+    }
+}
+
+/// Whether the dotted `path` is `key` or lies below it.
+pub fn is_under(path: &str, key: &str) -> bool {
+    path.strip_prefix(key).is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+/// The spelling of `key` that [`join_key`] produces, so differently escaped
+/// spellings of one path compare equal.
+pub fn canonical_key(key: &str) -> Cow<'_, str> {
+    if !key.contains('\\') {
+        return Cow::Borrowed(key);
+    }
+    let segments: Vec<String> = key_segments(key).map(|segment| escape_segment(&segment).into_owned()).collect();
+    Cow::Owned(segments.join("."))
+}
+
 pub fn get_value_by_key<'a>(
     map: &'a HashMap<String, TranslationValue>,
     key: &str,
 ) -> Result<&'a TranslationValue, String> {
-    let keys: Vec<&str> = key.split('.').collect();
+    let mut segments = key_segments(key);
+    let mut last = segments.next().unwrap_or_default();
     let mut current = map;
 
-    for k in &keys[..keys.len() - 1] {
-        if let Some(TranslationValue::Nested(next_map)) = current.get(*k) {
+    for next in segments {
+        if let Some(TranslationValue::Nested(next_map)) = current.get(last.as_ref()) {
             current = next_map;
         } else {
-            return Err(format!("Key '{}' not found", k));
+            return Err(format!("Key '{}' not found", last));
         }
+        last = next;
     }
 
     current
-        .get(&keys.last().unwrap().to_string())
+        .get(last.as_ref())
         .ok_or_else(|| {
             format!(
                 "Key '{}' not found in the provided translation map",
-                keys.last().unwrap()
+                last
             )
         })
 }
 
-pub fn update_translation_recursive(
+pub fn update_translation_recursive<S: AsRef<str>>(
     current_map: &mut HashMap<String, TranslationValue>,
-    keys: &[&str],
+    keys: &[S],
     value: TranslationValue,
-) -> Result<(), JsValue> {
+) -> Result<(), String> {
     if keys.len() == 1 {
         // Base case: if there's only one key left, insert the value
-        current_map.insert(keys[0].to_string(), value);
+        current_map.insert(keys[0].as_ref().to_string(), value);
         Ok(())
     } else {
         // Recursive case: traverse deeper into the nested structure
-        let next_key = keys[0];
+        let next_key = keys[0].as_ref();
         let entry = current_map
             .entry(next_key.to_string())
             .or_insert_with(|| TranslationValue::Nested(HashMap::new()));
@@ -46,7 +247,7 @@ pub fn update_translation_recursive(
         // Check that the entry is not a string (i.e., it's a Nested variant)
         match entry {
             TranslationValue::String(_) => Err(
-                JsValue::from("Invalid key path: found a string where a nested map was expected".to_string()),
+                "Invalid key path: found a string where a nested map was expected".to_string(),
             ),
             TranslationValue::Nested(ref mut nested_map) => {
                 // Recursively call for the next level
@@ -56,42 +257,42 @@ pub fn update_translation_recursive(
     }
 }
 
-pub fn remove_translation_recursive(
+/// Removes the value at `keys` and returns it. A path that does not exist,
+/// including one that runs through a string, is left untouched. With `prune`,
+/// nested maps left empty by the removal are removed too.
+pub fn remove_translation_recursive<S: AsRef<str>>(
     current_map: &mut HashMap<String, TranslationValue>,
-    keys: &[&str],
-) -> Result<(), JsValue> {
+    keys: &[S],
+    prune: bool,
+) -> Option<TranslationValue> {
     if keys.len() == 1 {
         // Base case: remove the key at the current level
-        current_map.remove(keys[0]);
-        Ok(())
-    } else {
-        // Recursive case: traverse deeper into the nested structure
-        let next_key = keys[0];
-        let entry = current_map
-            .entry(next_key.to_string())
-            .or_insert_with(|| TranslationValue::Nested(HashMap::new()));
+        return current_map.remove(keys[0].as_ref());
+    }
 
-        match entry {
-            TranslationValue::String(_) => Err(
-                JsValue::from("Invalid key path: found a string where a nested map was expected".to_string()),
-            ),
-            TranslationValue::Nested(ref mut nested_map) => {
-                // Recursively call for the next level
-                remove_translation_recursive(nested_map, &keys[1..])?;
-                Ok(())
+    // Recursive case: traverse deeper without creating missing levels
+    let removed = match current_map.get_mut(keys[0].as_ref()) {
+        Some(TranslationValue::Nested(nested_map)) => remove_translation_recursive(nested_map, &keys[1..], prune),
+        _ => None,
+    };
+    if prune && removed.is_some() {
+        if let Some(TranslationValue::Nested(nested_map)) = current_map.get(keys[0].as_ref()) {
+            if nested_map.is_empty() {
+                current_map.remove(keys[0].as_ref());
             }
         }
     }
+    removed
 }
 
-pub fn format_string(
+pub fn format_string<V: fmt::Display>(
     template: &str,
-    args: &HashMap<String, String>,
+    args: &HashMap<String, V>,
 ) -> Result<String, JsValue> {
     let mut result = template.to_string();
     for (key, value) in args {
         let placeholder = format!("{{{}}}", key);
-        result = result.replace(&placeholder, value);
+        result = result.replace(&placeholder, &value.to_string());
     }
     Ok(result)
 }
@@ -119,3 +320,33 @@ pub fn merge_translation_values(
         }
     }
 }
+
+
+/// Collects every string leaf under `value` as `(dotted path, text)` pairs,
+/// where `prefix` is the path of `value` itself.
+pub fn collect_leaves<'a>(prefix: &str, value: &'a TranslationValue, out: &mut Vec<(String, &'a str)>) {
+    match value {
+        TranslationValue::String(s) => out.push((prefix.to_string(), s)),
+        TranslationValue::Nested(map) => {
+            for (key, child) in map {
+                collect_leaves(&join_key(prefix, key), child, out);
+            }
+        }
+    }
+}
+
+/// Collects every string leaf of a locale's map, see [`collect_leaves`].
+pub fn map_leaves(map: &HashMap<String, TranslationValue>) -> Vec<(String, &str)> {
+    let mut leaves = Vec::new();
+    for (key, value) in map {
+        collect_leaves(&escape_segment(key), value, &mut leaves);
+    }
+    leaves
+}
+
+/// Separates a key from its disambiguation context, e.g. `open#verb`.
+pub const CONTEXT_SEPARATOR: char = '#';
+
+pub fn context_key(key: &str, context: &str) -> String {
+    format!("{}{}{}", key, CONTEXT_SEPARATOR, context)
+}
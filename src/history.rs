@@ -0,0 +1,161 @@
+//! Undo/redo history of translation edits.
+//!
+//! Every mutation becomes a step holding, for each key it wrote or removed,
+//! the value, key metadata and Fluent flag before and after, plus the locales
+//! it created or removed. Undo writes the `before` values back in reverse
+//! order and redo replays the `after` values; both move the step to the other
+//! stack. The oldest steps are dropped beyond the configured depth.
+
+use std::collections::{HashMap, VecDeque};
+use crate::batch::BatchOp;
+use crate::helpers::collect_leaves;
+use crate::metadata::KeyMetadata;
+use crate::TranslationValue;
+
+pub const DEFAULT_HISTORY_DEPTH: usize = 100;
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub locale: String,
+    pub key: String,
+    pub before: Option<TranslationValue>,
+    pub after: Option<TranslationValue>,
+    /// Metadata of the key and of every key below it.
+    pub metadata_before: Vec<(String, KeyMetadata)>,
+    pub metadata_after: Vec<(String, KeyMetadata)>,
+    /// Whether the key lies in a Fluent message.
+    pub fluent_before: bool,
+    pub fluent_after: bool,
+}
+
+/// Whether a locale existed before and after a step.
+#[derive(Clone, Debug)]
+pub struct LocaleEntry {
+    pub locale: String,
+    pub before: bool,
+    pub after: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Step {
+    pub entries: Vec<Entry>,
+    /// Locales the step created or removed.
+    pub locales: Vec<LocaleEntry>,
+}
+
+impl Step {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.locales.is_empty()
+    }
+}
+
+pub struct History {
+    depth: usize,
+    undo: VecDeque<Step>,
+    redo: Vec<Step>,
+}
+
+impl History {
+    pub fn new(depth: usize) -> History {
+        History { depth, undo: VecDeque::new(), redo: Vec::new() }
+    }
+
+    /// Records a new edit, which invalidates the redo stack.
+    pub fn push(&mut self, step: Step) {
+        self.redo.clear();
+        self.push_undo(step);
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        self.trim();
+    }
+
+    pub fn push_undo(&mut self, step: Step) {
+        self.undo.push_back(step);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.undo.len() > self.depth {
+            self.undo.pop_front();
+        }
+    }
+
+    pub fn pop_undo(&mut self) -> Option<Step> {
+        self.undo.pop_back()
+    }
+
+    pub fn push_redo(&mut self, step: Step) {
+        self.redo.push(step);
+    }
+
+    pub fn pop_redo(&mut self) -> Option<Step> {
+        self.redo.pop()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// The net effect of the undoable steps as `set`/`delete` operations,
+    /// one per key, ordered by each key's last edit so writes to a parent and
+    /// its children replay in the right order.
+    pub fn patch(&self) -> Vec<BatchOp> {
+        // (first entry, last entry) per key.
+        let mut entries: Vec<(&Entry, &Entry)> = Vec::new();
+        for entry in self.undo.iter().flat_map(|step| &step.entries) {
+            let same_key = |(first, _): &(&Entry, &Entry)| first.locale == entry.locale && first.key == entry.key;
+            let first = match entries.iter().position(same_key) {
+                Some(i) => entries.remove(i).0,
+                None => entry,
+            };
+            entries.push((first, entry));
+        }
+        entries
+            .into_iter()
+            // Skip keys whose only edits were to their metadata.
+            .filter(|(first, last)| !changed_keys(&last.key, first.before.as_ref(), last.after.as_ref()).is_empty())
+            .filter_map(|(first, last)| match (&first.before, &last.after) {
+                (_, Some(value)) => Some(BatchOp::Set {
+                    locale: last.locale.clone(),
+                    key: last.key.clone(),
+                    value: value.clone(),
+                }),
+                // Created and removed again: nothing to replay.
+                (None, None) => None,
+                (Some(_), None) => Some(BatchOp::Delete { locale: last.locale.clone(), key: last.key.clone() }),
+            })
+            .collect()
+    }
+}
+
+/// The string keys whose text differs between `before` and `after`, both
+/// stored at `key`.
+pub fn changed_keys(key: &str, before: Option<&TranslationValue>, after: Option<&TranslationValue>) -> Vec<String> {
+    fn leaves<'a>(key: &str, value: Option<&'a TranslationValue>) -> HashMap<String, &'a str> {
+        let mut leaves = Vec::new();
+        if let Some(value) = value {
+            collect_leaves(key, value, &mut leaves);
+        }
+        leaves.into_iter().collect()
+    }
+
+    let before = leaves(key, before);
+    let after = leaves(key, after);
+    let mut keys: Vec<String> = before
+        .iter()
+        .filter(|(key, text)| after.get(*key) != Some(text))
+        .map(|(key, _)| key.clone())
+        .collect();
+    keys.extend(after.keys().filter(|key| !before.contains_key(*key)).cloned());
+    keys
+}
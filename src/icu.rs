@@ -0,0 +1,282 @@
+//! A parser for ICU MessageFormat patterns.
+//!
+//! Plain `{name}` placeholders, as used throughout this crate, are the
+//! simplest ICU arguments, so every translation string can be checked with the
+//! same parser. Supported argument types are `number`, `date`, `time`,
+//! `plural`, `selectordinal` and `select`; apostrophes quote literal syntax
+//! characters as in ICU (`'{'`, `''`).
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    Text(String),
+    /// `#` inside a plural option: the plural value.
+    Pound,
+    Argument(Argument),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Argument {
+    pub name: String,
+    pub kind: ArgumentKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentKind {
+    Plain,
+    Number(Option<String>),
+    Date(Option<String>),
+    Time(Option<String>),
+    Plural {
+        ordinal: bool,
+        offset: f64,
+        options: Vec<(String, Vec<Node>)>,
+    },
+    Select(Vec<(String, Vec<Node>)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IcuError {
+    /// Offset of the error in chars.
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for IcuError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+pub fn parse(text: &str) -> Result<Vec<Node>, IcuError> {
+    let mut parser = Parser { chars: text.chars().collect(), pos: 0 };
+    let nodes = parser.message(false)?;
+    match parser.peek() {
+        Some('}') => Err(parser.error("unmatched '}'")),
+        _ => Ok(nodes),
+    }
+}
+
+/// Every argument in `nodes`, including those nested in plural and select
+/// options, in order of appearance.
+pub fn arguments(nodes: &[Node]) -> Vec<&Argument> {
+    fn walk<'a>(nodes: &'a [Node], out: &mut Vec<&'a Argument>) {
+        for node in nodes {
+            if let Node::Argument(argument) = node {
+                out.push(argument);
+                if let ArgumentKind::Plural { options, .. } | ArgumentKind::Select(options) = &argument.kind {
+                    for (_, message) in options {
+                        walk(message, out);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(nodes, &mut out);
+    out
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn error(&self, message: &str) -> IcuError {
+        IcuError { offset: self.pos, message: message.to_string() }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Parses text and arguments up to an unmatched `}` or the end of input.
+    fn message(&mut self, in_plural: bool) -> Result<Vec<Node>, IcuError> {
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '}' => break,
+                '{' => {
+                    if !text.is_empty() {
+                        nodes.push(Node::Text(std::mem::take(&mut text)));
+                    }
+                    nodes.push(Node::Argument(self.argument()?));
+                }
+                '#' if in_plural => {
+                    if !text.is_empty() {
+                        nodes.push(Node::Text(std::mem::take(&mut text)));
+                    }
+                    nodes.push(Node::Pound);
+                    self.pos += 1;
+                }
+                '\'' => self.apostrophe(in_plural, &mut text),
+                c => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+        Ok(nodes)
+    }
+
+    /// `''` is a literal apostrophe and an apostrophe before a syntax
+    /// character starts quoted text; any other apostrophe is literal.
+    fn apostrophe(&mut self, in_plural: bool, text: &mut String) {
+        self.pos += 1;
+        match self.peek() {
+            Some('\'') => {
+                text.push('\'');
+                self.pos += 1;
+            }
+            Some('{' | '}' | '|') => self.quoted(text),
+            Some('#') if in_plural => self.quoted(text),
+            _ => text.push('\''),
+        }
+    }
+
+    fn quoted(&mut self, text: &mut String) {
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if c != '\'' {
+                text.push(c);
+            } else if self.peek() == Some('\'') {
+                text.push('\'');
+                self.pos += 1;
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn word(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| !c.is_whitespace() && !matches!(c, '{' | '}' | ',' | '\'' | '#'))
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn expect(&mut self, c: char, message: &str) -> Result<(), IcuError> {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(message))
+        }
+    }
+
+    fn argument(&mut self) -> Result<Argument, IcuError> {
+        let start = self.pos;
+        self.pos += 1;
+        self.skip_whitespace();
+        let name = self.word();
+        if name.is_empty() {
+            return Err(match self.peek() {
+                None => IcuError { offset: start, message: "unclosed '{'".to_string() },
+                _ => self.error("expected an argument name"),
+            });
+        }
+        self.skip_whitespace();
+
+        let kind = match self.peek() {
+            Some('}') => ArgumentKind::Plain,
+            Some(',') => {
+                self.pos += 1;
+                self.skip_whitespace();
+                let kind = self.word();
+                self.skip_whitespace();
+                self.typed_argument(&name, &kind)?
+            }
+            None => return Err(IcuError { offset: start, message: "unclosed '{'".to_string() }),
+            _ => return Err(self.error("expected ',' or '}' after the argument name")),
+        };
+        self.expect('}', "expected '}' to close the argument")?;
+        Ok(Argument { name, kind })
+    }
+
+    fn typed_argument(&mut self, name: &str, kind: &str) -> Result<ArgumentKind, IcuError> {
+        match kind {
+            "number" | "date" | "time" => {
+                let style = if self.peek() == Some(',') {
+                    self.pos += 1;
+                    let start = self.pos;
+                    while self.peek().is_some_and(|c| c != '}' && c != '{') {
+                        self.pos += 1;
+                    }
+                    let style: String = self.chars[start..self.pos].iter().collect();
+                    Some(style.trim().to_string()).filter(|style| !style.is_empty())
+                } else {
+                    None
+                };
+                Ok(match kind {
+                    "number" => ArgumentKind::Number(style),
+                    "date" => ArgumentKind::Date(style),
+                    _ => ArgumentKind::Time(style),
+                })
+            }
+            "plural" | "selectordinal" | "select" => {
+                self.expect(',', &format!("expected ',' and options after '{}'", kind))?;
+                let plural = kind != "select";
+                let mut offset = 0.0;
+                self.skip_whitespace();
+                if plural && self.chars[self.pos..].starts_with(&['o', 'f', 'f', 's', 'e', 't', ':']) {
+                    self.pos += 7;
+                    self.skip_whitespace();
+                    offset = self
+                        .word()
+                        .parse()
+                        .map_err(|_| self.error("invalid plural offset"))?;
+                }
+                let options = self.options(plural)?;
+                if !options.iter().any(|(selector, _)| selector == "other") {
+                    return Err(self.error(&format!("{} argument '{}' has no 'other' option", kind, name)));
+                }
+                Ok(if plural {
+                    ArgumentKind::Plural { ordinal: kind == "selectordinal", offset, options }
+                } else {
+                    ArgumentKind::Select(options)
+                })
+            }
+            "" => Err(self.error("expected an argument type")),
+            other => Err(self.error(&format!("unknown argument type '{}'", other))),
+        }
+    }
+
+    fn options(&mut self, plural: bool) -> Result<Vec<(String, Vec<Node>)>, IcuError> {
+        let mut options: Vec<(String, Vec<Node>)> = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if matches!(self.peek(), Some('}') | None) {
+                return Ok(options);
+            }
+            let selector = self.word();
+            if selector.is_empty() {
+                return Err(self.error("expected an option selector"));
+            }
+            if options.iter().any(|(existing, _)| *existing == selector) {
+                return Err(self.error(&format!("duplicate option '{}'", selector)));
+            }
+            self.skip_whitespace();
+            self.expect('{', &format!("expected '{{' after option '{}'", selector))?;
+            let message = self.message(plural)?;
+            self.expect('}', "unclosed option message")?;
+            options.push((selector, message));
+        }
+    }
+}
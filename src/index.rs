@@ -0,0 +1,154 @@
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use crate::helpers::{canonical_key, collect_leaves, escape_segment};
+use crate::TranslationValue;
+
+/// Flattened view of the translation tree: `(locale, dotted path)` → interned
+/// string. Paths are canonical (see `helpers::join_key`), so a literal
+/// `"a.b"` key and a nested `a` → `b` never share an entry.
+///
+/// The nested tree stays the source of truth; the index only mirrors its string
+/// leaves so hot-path lookups are a single hash probe.
+#[derive(Default)]
+pub struct KeyIndex {
+    entries: HashMap<IndexKey, Arc<str>>,
+    strings: HashSet<Arc<str>>,
+}
+
+impl KeyIndex {
+    pub fn get(&self, locale: &str, key: &str) -> Option<&Arc<str>> {
+        let key = canonical_key(key);
+        self.entries.get(&(locale, key.as_ref()) as &dyn LocalePath)
+    }
+
+    /// Indexes every string leaf of `value`, which lives at `prefix` in the tree.
+    pub fn insert_subtree(&mut self, locale: &str, prefix: &str, value: &TranslationValue) {
+        let mut leaves = Vec::new();
+        collect_leaves(&canonical_key(prefix), value, &mut leaves);
+
+        for (path, text) in leaves {
+            let interned = self.intern(text);
+            let key = IndexKey { locale: locale.to_string(), path };
+            if let Some(previous) = self.entries.insert(key, interned) {
+                Self::release(&mut self.strings, previous);
+            }
+        }
+    }
+
+    /// Drops the entries for every string leaf of `value`, which lived at `prefix`.
+    pub fn remove_subtree(&mut self, locale: &str, prefix: &str, value: &TranslationValue) {
+        let mut leaves = Vec::new();
+        collect_leaves(&canonical_key(prefix), value, &mut leaves);
+
+        for (path, _) in leaves {
+            if let Some(text) = self.entries.remove(&(locale, path.as_str()) as &dyn LocalePath) {
+                Self::release(&mut self.strings, text);
+            }
+        }
+    }
+
+    /// Rebuilds the entries of a locale from its full translation map.
+    pub fn rebuild_locale(&mut self, locale: &str, map: &HashMap<String, TranslationValue>) {
+        self.remove_locale(locale);
+        for (key, value) in map {
+            self.insert_subtree(locale, &escape_segment(key), value);
+        }
+    }
+
+    pub fn remove_locale(&mut self, locale: &str) {
+        let strings = &mut self.strings;
+        self.entries.retain(|key, text| {
+            if key.locale != locale {
+                return true;
+            }
+            if Arc::strong_count(text) == 2 {
+                strings.remove(text);
+            }
+            false
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.strings.clear();
+    }
+
+    fn intern(&mut self, text: &str) -> Arc<str> {
+        if let Some(existing) = self.strings.get(text) {
+            return existing.clone();
+        }
+        let interned: Arc<str> = Arc::from(text);
+        self.strings.insert(interned.clone());
+        interned
+    }
+
+    // The interner holds one reference itself; once the last path using a
+    // string goes away, drop it from the interner too.
+    fn release(strings: &mut HashSet<Arc<str>>, text: Arc<str>) {
+        if Arc::strong_count(&text) == 2 {
+            strings.remove(&text);
+        }
+    }
+}
+
+#[derive(PartialEq, Eq)]
+struct IndexKey {
+    locale: String,
+    path: String,
+}
+
+// Lets the map be probed with borrowed `(&str, &str)` pairs: both key types
+// hash and compare through `LocalePath`.
+trait LocalePath {
+    fn locale(&self) -> &str;
+    fn path(&self) -> &str;
+}
+
+impl LocalePath for IndexKey {
+    fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl LocalePath for (&str, &str) {
+    fn locale(&self) -> &str {
+        self.0
+    }
+
+    fn path(&self) -> &str {
+        self.1
+    }
+}
+
+impl<'a> Borrow<dyn LocalePath + 'a> for IndexKey {
+    fn borrow(&self) -> &(dyn LocalePath + 'a) {
+        self
+    }
+}
+
+impl Hash for IndexKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self as &dyn LocalePath).hash(state)
+    }
+}
+
+impl Hash for dyn LocalePath + '_ {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.locale().hash(state);
+        self.path().hash(state);
+    }
+}
+
+impl PartialEq for dyn LocalePath + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        self.locale() == other.locale() && self.path() == other.path()
+    }
+}
+
+impl Eq for dyn LocalePath + '_ {}
@@ -1,19 +1,76 @@
+mod arb;
+mod batch;
+mod bundle;
+mod coverage;
+mod events;
+mod fluent;
+mod formats;
+mod gettext;
 mod helpers;
+mod history;
+mod icu;
+mod index;
+mod markup;
+mod merge;
+mod message;
+mod metadata;
+mod options;
+mod patch;
+mod placeholders;
+mod mobile;
+mod search;
+mod snapshot;
+mod validate;
+mod xliff;
+mod xml;
 
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
-use std::collections::HashMap;
-use std::sync::RwLock;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::rc::Rc;
+use std::ops::{Deref, DerefMut};
+use std::sync::{RwLock, RwLockWriteGuard};
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::wasm_bindgen;
 use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{Request, RequestInit, Response};
-use crate::helpers::{format_string, get_value_by_key, merge_translation_values, remove_translation_recursive, update_translation_recursive};
+use crate::arb::{parse_arb, write_arb};
+use crate::batch::{apply_ops, BatchOp};
+use crate::bundle::{decode_bundle, encode_bundle_json};
+use crate::coverage::coverage;
+use crate::events::{emit, ChangeEvent, Event, Listeners, LoadedEvent, LocaleChangedEvent};
+use crate::fluent::{parse_resource, Scope};
+use crate::formats::{parse_properties, parse_toml, parse_yaml, Format};
+use crate::gettext::{contains_plural_map, header_field, parse_mo, parse_po, write_po, ImportReport, PluralForms, PoEntry, DEFAULT_PLURAL_FORMS};
+use crate::helpers::{
+    canonical_key, context_key, escape_segment, format_string, get_value_by_key, is_under, key_segments, split_key, FormatArg, remove_translation_recursive,
+    update_translation_recursive,
+};
+use crate::history::{changed_keys, Entry, History, LocaleEntry, Step, DEFAULT_HISTORY_DEPTH};
+use crate::index::KeyIndex;
+use crate::markup::{escape_args, parse_markup, render_html, MarkupNode};
+use crate::merge::{merge_entry, type_conflicts, ConflictMode, MergeOptions, MergeStrategy};
+use crate::message::{format_to_parts, join_parts, MessageCache, Part};
+use crate::metadata::KeyMetadata;
+use crate::mobile::{
+    parse_android, parse_apple_strings, parse_stringsdict, resource_key, write_android, write_apple_strings, write_stringsdict,
+};
+use crate::options::{FormatMode, TranslationOptions, DEFAULT_MISSING_MARKER};
+use crate::patch::{diff_maps, patch_to_ops};
+use crate::placeholders::{check_arguments, placeholders};
+use crate::search::{count_keys, list_keys, search, SearchOptions};
+use crate::snapshot::{decode_snapshot_binary, Settings, Snapshot, SNAPSHOT_VERSION};
+use crate::validate::validate_locales;
+use crate::xliff::{parse_xliff, write_xliff, XliffImportReport};
 
-#[derive(Serialize, Deserialize, Clone)]
+pub use crate::events::Subscription;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(untagged)]
 pub(crate) enum TranslationValue {
     String(String),
@@ -23,6 +80,350 @@ pub(crate) enum TranslationValue {
 #[derive(Serialize, Deserialize)]
 struct Translations {
     translations: HashMap<String, HashMap<String, TranslationValue>>,
+    #[serde(skip)]
+    index: Option<KeyIndex>,
+    /// Top-level keys imported from Fluent resources, per locale. Only these
+    /// are formatted as Fluent messages.
+    #[serde(skip)]
+    fluent_keys: HashMap<String, HashSet<String>>,
+    #[serde(skip)]
+    metadata: HashMap<String, HashMap<String, KeyMetadata>>,
+    #[serde(skip)]
+    format_mode: FormatMode,
+    #[serde(skip)]
+    missing_marker: Option<String>,
+    #[serde(skip)]
+    locale: Option<String>,
+    /// Whether mutations record the keys they touch; set once a `change`
+    /// listener is added.
+    #[serde(skip)]
+    track_changes: bool,
+    #[serde(skip)]
+    history: Option<History>,
+    /// Keys and locales touched by the mutation in progress, with their
+    /// previous state.
+    #[serde(skip)]
+    touched: Step,
+    #[serde(skip)]
+    messages: MessageCache,
+}
+
+/// Write access to the translations for one mutation, handed to
+/// `I18n::commit` when it succeeds. A mutation that returns early with an
+/// error drops it instead, which forgets the keys touched so far so they do
+/// not end up in the next mutation's change events or history step.
+struct Mutation<'a>(RwLockWriteGuard<'a, Translations>);
+
+impl Deref for Mutation<'_> {
+    type Target = Translations;
+
+    fn deref(&self) -> &Translations {
+        &self.0
+    }
+}
+
+impl DerefMut for Mutation<'_> {
+    fn deref_mut(&mut self) -> &mut Translations {
+        &mut self.0
+    }
+}
+
+impl Drop for Mutation<'_> {
+    fn drop(&mut self) {
+        self.0.touched = Step::default();
+    }
+}
+
+impl Translations {
+    /// Drops the index entries for whatever currently lives at `key`.
+    fn unindex_key(&mut self, locale: &str, key: &str) {
+        if let (Some(index), Some(map)) = (self.index.as_mut(), self.translations.get(locale)) {
+            if let Ok(value) = get_value_by_key(map, key) {
+                index.remove_subtree(locale, key, value);
+            }
+        }
+    }
+
+    /// Indexes whatever currently lives at `key`.
+    fn index_key(&mut self, locale: &str, key: &str) {
+        if let (Some(index), Some(map)) = (self.index.as_mut(), self.translations.get(locale)) {
+            if let Ok(value) = get_value_by_key(map, key) {
+                index.insert_subtree(locale, key, value);
+            }
+        }
+    }
+
+    fn tracking(&self) -> bool {
+        self.track_changes || self.history.is_some()
+    }
+
+    /// Remembers the value at `key` before the current mutation writes or
+    /// removes it.
+    fn touch(&mut self, locale: &str, key: &str) {
+        if !self.tracking() || self.touched.entries.iter().any(|entry| entry.locale == locale && entry.key == key) {
+            return;
+        }
+        self.touch_locale_entry(locale);
+        let before = self.translations.get(locale).and_then(|map| get_value_by_key(map, key).ok()).cloned();
+        self.touched.entries.push(Entry {
+            locale: locale.to_string(),
+            key: key.to_string(),
+            before,
+            after: None,
+            metadata_before: self.metadata_under(locale, key),
+            metadata_after: Vec::new(),
+            fluent_before: self.is_fluent(locale, key),
+            fluent_after: false,
+        });
+    }
+
+    /// Remembers whether `locale` existed before the current mutation.
+    fn touch_locale_entry(&mut self, locale: &str) {
+        if self.touched.locales.iter().any(|entry| entry.locale == locale) {
+            return;
+        }
+        self.touched.locales.push(LocaleEntry {
+            locale: locale.to_string(),
+            before: self.translations.contains_key(locale),
+            after: false,
+        });
+    }
+
+    /// The map of `locale`, created empty if needed and recorded as touched.
+    fn locale_mut(&mut self, locale: &str) -> &mut HashMap<String, TranslationValue> {
+        if self.tracking() {
+            self.touch_locale_entry(locale);
+        }
+        self.translations.entry(locale.to_string()).or_default()
+    }
+
+    /// Touches `locale` itself and every top-level key in it.
+    fn touch_locale(&mut self, locale: &str) {
+        if !self.tracking() {
+            return;
+        }
+        self.touch_locale_entry(locale);
+        let keys: Vec<String> = self
+            .translations
+            .get(locale)
+            .map(|map| map.keys().map(|key| escape_segment(key).into_owned()).collect())
+            .unwrap_or_default();
+        for key in keys {
+            self.touch(locale, &key);
+        }
+    }
+
+    /// Completes the current mutation, returning the string keys it changed
+    /// per locale and the touched keys and locales with their state before
+    /// and after.
+    fn finish_mutation(&mut self) -> (BTreeMap<String, BTreeSet<String>>, Step) {
+        let mut step = std::mem::take(&mut self.touched);
+        let mut changes: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        step.locales.retain_mut(|entry| {
+            entry.after = self.translations.contains_key(&entry.locale);
+            entry.before != entry.after
+        });
+        step.entries.retain_mut(|entry| {
+            entry.after = self.translations.get(&entry.locale).and_then(|map| get_value_by_key(map, &entry.key).ok()).cloned();
+            entry.metadata_after = self.metadata_under(&entry.locale, &entry.key);
+            entry.fluent_after = self.is_fluent(&entry.locale, &entry.key);
+            let keys = changed_keys(&entry.key, entry.before.as_ref(), entry.after.as_ref());
+            // Metadata edits are undoable but do not change any string.
+            let kept = !keys.is_empty() || entry.metadata_before != entry.metadata_after;
+            if !keys.is_empty() {
+                changes.entry(entry.locale.clone()).or_default().extend(keys);
+            }
+            kept
+        });
+        (changes, step)
+    }
+
+    /// Replaces the metadata of `key` and the keys below it with `metadata`,
+    /// and sets the Fluent flag of the message `key` lies in, as recorded in a
+    /// history step.
+    fn restore_entry(&mut self, locale: &str, key: &str, metadata: &[(String, KeyMetadata)], fluent: bool) {
+        self.remove_metadata(locale, key);
+        for (path, metadata) in metadata {
+            self.add_metadata(locale, path, metadata.clone());
+        }
+        let Some(name) = key_segments(key).next().map(Cow::into_owned) else {
+            return;
+        };
+        let exists = self.translations.get(locale).is_some_and(|map| map.contains_key(&name));
+        if fluent && exists {
+            self.fluent_keys.entry(locale.to_string()).or_default().insert(name);
+        } else if let Some(keys) = self.fluent_keys.get_mut(locale) {
+            keys.remove(&name);
+            if keys.is_empty() {
+                self.fluent_keys.remove(locale);
+            }
+        }
+    }
+
+    /// Creates `locale` empty when `exists`, or drops it with its metadata
+    /// and Fluent flags otherwise.
+    fn restore_locale(&mut self, locale: &str, exists: bool) {
+        self.touch_locale(locale);
+        if exists {
+            self.translations.entry(locale.to_string()).or_default();
+            return;
+        }
+        self.translations.remove(locale);
+        self.fluent_keys.remove(locale);
+        self.metadata.remove(locale);
+        if let Some(index) = self.index.as_mut() {
+            index.remove_locale(locale);
+        }
+    }
+
+    /// Replaces the tree of `locale` with `map`, a copy in which `keys` were
+    /// written.
+    fn swap_locale(&mut self, locale: &str, map: HashMap<String, TranslationValue>, keys: &[String]) {
+        self.locale_mut(locale);
+        for key in keys {
+            self.touch(locale, key);
+        }
+        if let Some(index) = self.index.as_mut() {
+            index.rebuild_locale(locale, &map);
+        }
+        self.translations.insert(locale.to_string(), map);
+        for key in keys {
+            self.forget_fluent(locale, key);
+        }
+    }
+
+    /// Writes `value` under the top-level key `name`, which is taken literally
+    /// rather than as a dotted path.
+    fn set_entry(&mut self, locale: &str, name: String, value: TranslationValue) {
+        let path = escape_segment(&name).into_owned();
+        self.touch(locale, &path);
+        self.forget_fluent(locale, &path);
+        self.unindex_key(locale, &path);
+        self.locale_mut(locale).insert(name, value);
+        self.index_key(locale, &path);
+    }
+
+    /// Whether `key` lies in a message imported from a Fluent resource.
+    fn is_fluent(&self, locale: &str, key: &str) -> bool {
+        self.fluent_keys
+            .get(locale)
+            .is_some_and(|keys| key_segments(key).next().is_some_and(|name| keys.contains(name.as_ref())))
+    }
+
+    /// Drops the Fluent flag of the message `key` lies in when the write or
+    /// removal at `key` replaced the whole message, or left it missing.
+    /// Edits below a message, such as one of its attributes, keep the flag.
+    fn forget_fluent(&mut self, locale: &str, key: &str) {
+        let mut segments = key_segments(key);
+        let Some(name) = segments.next() else {
+            return;
+        };
+        let replaced = segments.next().is_none();
+        let exists = self.translations.get(locale).is_some_and(|map| map.contains_key(name.as_ref()));
+        if let Some(keys) = self.fluent_keys.get_mut(locale) {
+            if replaced || !exists {
+                keys.remove(name.as_ref());
+            }
+            if keys.is_empty() {
+                self.fluent_keys.remove(locale);
+            }
+        }
+    }
+
+    /// Merges imported metadata into whatever is already stored for `key`.
+    fn add_metadata(&mut self, locale: &str, key: &str, metadata: KeyMetadata) {
+        if metadata.is_empty() {
+            return;
+        }
+        let locale_metadata = self.metadata.entry(locale.to_string()).or_default();
+        locale_metadata.entry(key.to_string()).or_default().merge(metadata);
+    }
+
+    /// Drops the metadata of `key` and of every key below it.
+    fn remove_metadata(&mut self, locale: &str, key: &str) {
+        if let Some(metadata) = self.metadata.get_mut(locale) {
+            metadata.retain(|path, _| !is_under(path, key));
+        }
+    }
+
+    /// The paths at or below `key` that have metadata and hold a value.
+    fn described_paths(&self, locale: &str, key: &str) -> Vec<String> {
+        let Some(map) = self.translations.get(locale) else {
+            return Vec::new();
+        };
+        self.metadata_under(locale, key)
+            .into_iter()
+            .map(|(path, _)| path)
+            .filter(|path| get_value_by_key(map, path).is_ok())
+            .collect()
+    }
+
+    /// Removes the metadata of those `paths` that no longer hold a value.
+    fn prune_metadata(&mut self, locale: &str, paths: &[String]) {
+        let (Some(map), Some(metadata)) = (self.translations.get(locale), self.metadata.get_mut(locale)) else {
+            return;
+        };
+        for path in paths {
+            if get_value_by_key(map, path).is_err() {
+                metadata.remove(path);
+            }
+        }
+    }
+
+    /// The metadata of `key` and of every key below it, sorted by path.
+    fn metadata_under(&self, locale: &str, key: &str) -> Vec<(String, KeyMetadata)> {
+        let mut metadata: Vec<(String, KeyMetadata)> = self
+            .metadata
+            .get(locale)
+            .map(|metadata| {
+                metadata
+                    .iter()
+                    .filter(|(path, _)| is_under(path, key))
+                    .map(|(path, metadata)| (path.clone(), metadata.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        metadata.sort_by(|a, b| a.0.cmp(&b.0));
+        metadata
+    }
+
+    fn contains_key(&self, locale: &str, key: &str) -> bool {
+        self.indexed(locale, key).is_some()
+            || self
+                .translations
+                .get(locale)
+                .is_some_and(|map| get_value_by_key(map, key).is_ok())
+    }
+
+    /// Formats a string by `{name}` substitution or, with `icu`, as an ICU
+    /// message, falling back to substitution for text that does not parse.
+    fn format_text(&self, text: &str, locale: &str, args: &HashMap<String, FormatArg>, icu: bool) -> Result<Vec<Part>, JsValue> {
+        if icu {
+            if let Some(nodes) = self.messages.parse(text) {
+                return Ok(format_to_parts(&nodes, locale, args));
+            }
+        }
+        Ok(vec![Part::literal(format_string(text, args)?)])
+    }
+
+    /// Picks `key#context` when a context is given and that entry exists,
+    /// otherwise the context-free `key`.
+    fn resolve_key<'a>(&self, locale: &str, key: &'a str, context: Option<&str>) -> Cow<'a, str> {
+        if let Some(context) = context {
+            let qualified = context_key(key, &escape_segment(context));
+            if self.contains_key(locale, &qualified) {
+                return Cow::Owned(qualified);
+            }
+        }
+        Cow::Borrowed(key)
+    }
+
+    fn indexed(&self, locale: &str, key: &str) -> Option<&str> {
+        self.index
+            .as_ref()
+            .and_then(|index| index.get(locale, key))
+            .map(|text| &**text)
+    }
 }
 
 #[wasm_bindgen]
@@ -31,9 +432,23 @@ extern "C" {
     fn log(s: &str);
 }
 
+/// A translation store bound to the JavaScript thread.
+///
+/// Listeners are JS functions shared through an `Rc`, so `I18n` is neither
+/// `Send` nor `Sync`. The lock around the translations therefore never sees
+/// another thread; it marks each read or mutation, and is never held while
+/// calling into JavaScript, so listeners and tag callbacks can call back into
+/// the instance.
 #[wasm_bindgen]
 pub struct I18n {
     translations: RwLock<Translations>,
+    listeners: Rc<RefCell<Listeners>>,
+}
+
+impl Default for I18n {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[wasm_bindgen]
@@ -49,7 +464,17 @@ impl I18n {
     pub fn new() -> I18n {
         I18n { translations: RwLock::new(Translations {
             translations: HashMap::new(),
-        }), }
+            index: None,
+            fluent_keys: HashMap::new(),
+            metadata: HashMap::new(),
+            format_mode: FormatMode::Lenient,
+            missing_marker: None,
+            locale: None,
+            track_changes: false,
+            history: None,
+            touched: Step::default(),
+            messages: MessageCache::default(),
+        }), listeners: Rc::default() }
     }
 
     /// Retrieves all translations for all locales.
@@ -81,47 +506,117 @@ impl I18n {
 
     /// Sets translations for a given locale.
     ///
+    /// By default the incoming translations are deep-merged into the existing
+    /// ones. `{ strategy }` selects another merge: `"replace"` drops the
+    /// locale's existing translations, `"shallow"` replaces top-level keys as a
+    /// whole and `"keep-existing"` only adds keys that are missing. With
+    /// `{ onConflict: "error" }`, a key that would turn from a string into a
+    /// nested map (or the reverse) fails the call and nothing is written.
+    ///
     /// # Example
     /// ```js
     /// i18n.setTranslations('en', { "hello": "Hello" });
+    /// i18n.setTranslations('en', defaults, { strategy: 'keep-existing' });
+    /// i18n.setTranslations('en', incoming, { onConflict: 'error' });
     /// ```
     #[wasm_bindgen(js_name = setTranslations)]
-    pub fn set_translations(&self, locale: &str, obj: JsValue) -> Result<(), JsValue> {
+    pub fn set_translations_with_options(&self, locale: &str, obj: JsValue, options: JsValue) -> Result<(), JsValue> {
         // Преобразуем входной JsValue в HashMap
         let parsed: HashMap<String, TranslationValue> = serde_wasm_bindgen::from_value(obj)?;
+        let options = MergeOptions::from_js(options)?;
 
         // Получаем доступ к переводам
-        let mut translations = self.translations.write().unwrap();
+        let mut guard = self.mutate();
+        let translations = &mut *guard;
 
-        // Найти или создать место для данного `locale`
-        let locale_translations = translations
-            .translations
-            .entry(locale.to_string())
-            .or_default();
+        if options.on_conflict == ConflictMode::Error {
+            if let Some(existing) = translations.translations.get(locale) {
+                let conflicts = type_conflicts(existing, &parsed, options.strategy);
+                if !conflicts.is_empty() {
+                    let keys: Vec<String> = conflicts.iter().map(|key| format!("'{}'", key)).collect();
+                    return Err(JsValue::from(format!("Type conflicts at {}", keys.join(", "))));
+                }
+            }
+        }
+
+        if options.strategy == MergeStrategy::Replace {
+            translations.touch_locale(locale);
+            for key in parsed.keys() {
+                translations.touch(locale, &escape_segment(key));
+            }
+            // Metadata may describe keys without a string; record those too
+            // so an undo brings their metadata back.
+            let described: Vec<String> = translations
+                .metadata
+                .get(locale)
+                .map(|metadata| {
+                    metadata
+                        .keys()
+                        .filter_map(|path| key_segments(path).next().map(|name| escape_segment(&name).into_owned()))
+                        .collect()
+                })
+                .unwrap_or_default();
+            for key in described {
+                translations.touch(locale, &key);
+            }
+            if let Some(index) = translations.index.as_mut() {
+                index.rebuild_locale(locale, &parsed);
+            }
+            translations.translations.insert(locale.to_string(), parsed);
+            translations.fluent_keys.remove(locale);
+            translations.metadata.remove(locale);
+        } else {
+            // Найти или создать место для данного `locale`
+            translations.locale_mut(locale);
 
-        // Рекурсивно объединить переводы
-        for (key, value) in parsed {
-            locale_translations
-                .entry(key)
-                .and_modify(|existing_value| merge_translation_values(existing_value, value.clone()))
-                .or_insert(value);
+            // Объединить переводы согласно стратегии
+            for (key, value) in parsed {
+                let path = escape_segment(&key).into_owned();
+                translations.touch(locale, &path);
+                translations.unindex_key(locale, &path);
+                if options.strategy != MergeStrategy::KeepExisting {
+                    translations.forget_fluent(locale, &path);
+                }
+                // Metadata goes with the strings it describes: a shallow merge
+                // replaces the key as a whole, a deep one may drop leaves.
+                let described = match options.strategy {
+                    MergeStrategy::Deep => translations.described_paths(locale, &path),
+                    _ => Vec::new(),
+                };
+                if options.strategy == MergeStrategy::Shallow {
+                    translations.remove_metadata(locale, &path);
+                }
+                let map = translations.locale_mut(locale);
+                merge_entry(map, key, value, options.strategy);
+                translations.prune_metadata(locale, &described);
+                translations.index_key(locale, &path);
+            }
         }
 
+        self.commit(guard);
         Ok(())
     }
 
-
-
     /// Gets a translation for a given key and locale.
     ///
+    /// With `{ context }`, the context-specific entry `key#context` is returned
+    /// when it exists, falling back to the context-free `key`.
+    ///
     /// # Example
     /// ```js
     /// let translation = i18n.getTranslation('en', 'hello');
     /// console.log(translation); // "Hello"
+    /// i18n.getTranslation('en', 'open', { context: 'verb' }); // value of "open#verb", or of "open"
     /// ```
     #[wasm_bindgen(js_name = getTranslation)]
-    pub fn get_translation(&self, locale: &str, key: &str) -> Result<JsValue, JsValue> {
+    pub fn get_translation_with_options(&self, locale: &str, key: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options = TranslationOptions::from_js(options)?;
         let translations = self.translations.read().unwrap();
+        let key = translations.resolve_key(locale, key, options.context.as_deref());
+        let key = key.as_ref();
+        if let Some(text) = translations.indexed(locale, key) {
+            return Ok(JsValue::from_str(text));
+        }
         let value = get_value_by_key(
             translations
                 .translations
@@ -145,31 +640,39 @@ impl I18n {
     #[wasm_bindgen(js_name = hasTranslation)]
     pub fn has_translation(&self, locale: &str, key: &str) -> bool {
         let translations = self.translations.read().unwrap();
-        if let Some(translation_map) = translations.translations.get(locale) {
-            get_value_by_key(translation_map, key).is_ok()
-        } else {
-            false
-        }
+        translations.contains_key(locale, key)
     }
 
     /// Deletes a translation for a given key and locale.
     ///
+    /// Returns whether anything was removed; a key that does not exist leaves
+    /// the locale untouched. With `prune`, parent objects that the removal
+    /// leaves empty are removed as well.
+    ///
     /// # Example
     /// ```js
-    /// await i18n.delTranslation('en', 'hello');
+    /// i18n.delTranslation('en', 'hello'); // true
+    /// i18n.delTranslation('en', 'menu.help', true); // also drops an empty "menu"
     /// ```
     #[wasm_bindgen(js_name = delTranslation)]
-    pub fn del_translation(&self, locale: &str, key: &str) -> Result<(), JsValue> {
-        let mut translations = self.translations.write().unwrap();
-        let keys: Vec<&str> = key.split('.').collect();
+    pub fn del_translation_with_options(&self, locale: &str, key: &str, prune: Option<bool>) -> Result<bool, JsValue> {
+        let mut translations = self.mutate();
+        let keys = split_key(key);
 
+        translations.touch(locale, key);
+        translations.unindex_key(locale, key);
         let val = translations
             .translations
             .get_mut(locale)
             .ok_or("Locale not found")?;
-        remove_translation_recursive(val, &keys)?;
+        let removed = remove_translation_recursive(val, &keys, prune.unwrap_or(false)).is_some();
+        if removed {
+            translations.remove_metadata(locale, key);
+            translations.forget_fluent(locale, key);
+        }
 
-        Ok(())
+        self.commit(translations);
+        Ok(removed)
     }
 
 
@@ -200,8 +703,15 @@ impl I18n {
     /// ```
     #[wasm_bindgen(js_name = delTranslations)]
     pub fn del_translations(&self, locale: &str) -> Result<(), JsValue> {
-        let mut translations = self.translations.write().unwrap();
+        let mut translations = self.mutate();
+        translations.touch_locale(locale);
         translations.translations.remove(locale);
+        translations.fluent_keys.remove(locale);
+        translations.metadata.remove(locale);
+        if let Some(index) = translations.index.as_mut() {
+            index.remove_locale(locale);
+        }
+        self.commit(translations);
         Ok(())
     }
 
@@ -218,6 +728,54 @@ impl I18n {
         translations.translations.contains_key(locale)
     }
 
+    /// Lists the dotted keys of the strings in a locale, sorted. With a
+    /// `prefix`, only the keys at or below that path are listed.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.keys('en');         // ["menu.close", "menu.open", "title"]
+    /// i18n.keys('en', 'menu'); // ["menu.close", "menu.open"]
+    /// ```
+    pub fn keys(&self, locale: &str, prefix: Option<String>) -> Result<JsValue, JsValue> {
+        let translations = self.translations.read().unwrap();
+        let map = translations.translations.get(locale).ok_or("Locale not found")?;
+        to_js_object(&list_keys(map, prefix.as_deref()))
+    }
+
+    /// Finds the strings of a locale whose key or text matches `query`, as
+    /// `{ key, value }` pairs sorted by key. The query is a substring unless
+    /// `regex` is set; `ignoreCase` and `scope` (`"keys"`, `"values"` or
+    /// `"all"`) refine the match.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.search('en', 'open');
+    /// i18n.search('en', '^menu\\.', { regex: true, scope: 'keys' });
+    /// ```
+    pub fn search(&self, locale: &str, query: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options = SearchOptions::from_js(options)?;
+        let translations = self.translations.read().unwrap();
+        let map = translations.translations.get(locale).ok_or("Locale not found")?;
+        to_js_object(&search(map, query, &options)?)
+    }
+
+    /// Counts the string keys of every locale.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.keyCounts(); // { en: 120, de: 98 }
+    /// ```
+    #[wasm_bindgen(js_name = keyCounts)]
+    pub fn key_counts(&self) -> Result<JsValue, JsValue> {
+        let translations = self.translations.read().unwrap();
+        let counts: BTreeMap<&str, usize> = translations
+            .translations
+            .iter()
+            .map(|(locale, map)| (locale.as_str(), count_keys(map)))
+            .collect();
+        to_js_object(&counts)
+    }
+
     /// Clears all translations for all locales.
     ///
     /// # Example
@@ -226,19 +784,45 @@ impl I18n {
     /// ```
     #[wasm_bindgen(js_name = clearAllTranslations)]
     pub fn clear_all_translations(&self) -> Result<(), JsValue> {
-        let mut translations = self.translations.write().unwrap();
+        let mut translations = self.mutate();
+        let locales: Vec<String> = translations.translations.keys().cloned().collect();
+        for locale in &locales {
+            translations.touch_locale(locale);
+        }
         translations.translations.clear();
+        translations.fluent_keys.clear();
+        translations.metadata.clear();
+        if let Some(index) = translations.index.as_mut() {
+            index.clear();
+        }
+        self.commit(translations);
         Ok(())
     }
 
     /// Loads translations from a remote URL and updates the translation manager.
     ///
+    /// The document may be JSON, YAML (including the Rails layout with locales
+    /// at the top level), TOML or Java `.properties`. The format is taken from
+    /// `format` when given, otherwise from the response's `Content-Type` or the
+    /// URL's extension. Documents are keyed by locale unless `locale` is given,
+    /// in which case the whole document holds that locale's translations.
+    /// Dotted keys are paths, so `{ "menu.open": "Open" }` is stored under
+    /// `menu`. A document that conflicts with the stored translations, such
+    /// as a string where a map is needed, fails and changes nothing.
+    ///
     /// # Example
     /// ```js
     /// await i18n.loadTranslations('https://example.com/translations.json');
+    /// await i18n.loadTranslations('https://example.com/config/locales.yml');
+    /// await i18n.loadTranslations('https://example.com/messages_de.properties', null, 'de');
     /// ```
     #[wasm_bindgen(js_name = loadTranslations)]
-    pub async fn load_translations(&self, url: &str) -> Result<(), JsValue> {
+    pub async fn load_translations_with_options(
+        &self,
+        url: &str,
+        format: Option<String>,
+        locale: Option<String>,
+    ) -> Result<(), JsValue> {
         let opts = RequestInit::new();
         opts.set_method("GET");
 
@@ -246,22 +830,62 @@ impl I18n {
         let window = web_sys::window().ok_or("Window not found")?;
         let resp_value = JsFuture::from(window.fetch_with_request(&request)).await?;
         let resp: Response = resp_value.dyn_into()?;
-        let json = JsFuture::from(resp.json()?).await?;
-        let translations: HashMap<String, HashMap<String, TranslationValue>> =
-            serde_wasm_bindgen::from_value(json)?;
+        let format = match format {
+            Some(name) => Format::from_name(&name)?,
+            None => Format::detect(resp.headers().get("content-type")?.as_deref(), url),
+        };
+        let text = JsFuture::from(resp.text()?)
+            .await?
+            .as_string()
+            .ok_or("Response body is not text")?;
+        let document = format.parse(&text)?;
+
+        let translations: HashMap<String, HashMap<String, TranslationValue>> = match locale {
+            Some(locale) => std::iter::once((locale, document)).collect(),
+            None => document
+                .into_iter()
+                .map(|(locale, value)| match value {
+                    TranslationValue::Nested(map) => Ok((locale, map)),
+                    TranslationValue::String(_) => Err(format!("Expected translations for locale '{}'", locale)),
+                })
+                .collect::<Result<_, String>>()?,
+        };
 
+        let mut locales: Vec<String> = translations.keys().cloned().collect();
+        locales.sort();
+        let mut state = self.mutate();
+
+        // Dotted keys are paths; write into copies so a conflicting document
+        // changes nothing.
+        let mut working: HashMap<String, HashMap<String, TranslationValue>> = HashMap::new();
+        let mut written: Vec<(String, String)> = Vec::new();
         for (locale, translation) in translations {
+            let map = working
+                .entry(locale.clone())
+                .or_insert_with(|| state.translations.get(&locale).cloned().unwrap_or_default());
             for (key, value) in translation {
-                let mut translations = self.translations.write().unwrap();
-                let translation_map = translations.translations
-                    .get_mut(&locale)
-                    .ok_or("Locale not found")?;
-
-                let keys: Vec<&str> = key.split('.').collect();
-                update_translation_recursive(translation_map, &keys, value)?;
+                update_translation_recursive(map, &split_key(&key), value)
+                    .map_err(|error| format!("Locale '{}', key '{}': {}", locale, key, error))?;
+                written.push((locale.clone(), key));
             }
         }
-
+        for locale in working.keys() {
+            state.locale_mut(locale);
+        }
+        for (locale, key) in &written {
+            state.touch(locale, key);
+        }
+        for (locale, map) in working {
+            if let Some(index) = state.index.as_mut() {
+                index.rebuild_locale(&locale, &map);
+            }
+            state.translations.insert(locale, map);
+        }
+        for (locale, key) in &written {
+            state.forget_fluent(locale, key);
+        }
+        self.commit(state);
+        self.emit(Event::Loaded, &LoadedEvent { locales, url: Some(url.to_string()) });
         Ok(())
     }
 
@@ -275,41 +899,191 @@ impl I18n {
     pub fn update_translation(&self, locale: &str, key: &str, value: JsValue) -> Result<(), JsValue> {
         let parsed_value: TranslationValue = serde_wasm_bindgen::from_value(value)?;
 
-        let mut translations = self.translations.write().unwrap();
+        let mut translations = self.mutate();
+        translations.touch(locale, key);
+        translations.unindex_key(locale, key);
         let translation_map = translations
             .translations
             .get_mut(locale)
             .ok_or("Locale not found")?;
 
-        let keys: Vec<&str> = key.split('.').collect();
-        update_translation_recursive(translation_map, &keys, parsed_value)
+        let keys = split_key(key);
+        let result = update_translation_recursive(translation_map, &keys, parsed_value);
+        // Re-index even on failure, which leaves the old value in place.
+        translations.index_key(locale, key);
+        result?;
+        translations.forget_fluent(locale, key);
+        self.commit(translations);
+        Ok(())
+    }
+
+    /// Applies a list of operations atomically.
+    ///
+    /// Each operation is `{ op: "set", locale, key, value }` (creating the
+    /// locale if needed), `{ op: "update", locale, key, value }` (the locale
+    /// must exist) or `{ op: "delete", locale, key }` (the key must exist).
+    /// All operations are checked and applied under a single lock; if any of
+    /// them fails, none is applied and the error names the failing operation.
+    /// Listeners get one `change` event per affected locale for the batch.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.batch([
+    ///     { op: 'set', locale: 'en', key: 'menu.open', value: 'Open' },
+    ///     { op: 'update', locale: 'de', key: 'menu.open', value: 'Öffnen' },
+    ///     { op: 'delete', locale: 'en', key: 'menu.legacy' },
+    /// ]);
+    /// ```
+    pub fn batch(&self, ops: JsValue) -> Result<(), JsValue> {
+        self.apply_batch(serde_wasm_bindgen::from_value(ops)?)
+    }
+
+    /// Compares `locale` with another locale (given by name) or with an
+    /// incoming translation tree, returning the operations that turn `locale`
+    /// into the target: `{ op: "add", path, value }`, `{ op: "remove", path }`
+    /// and `{ op: "replace", path, value, oldValue }`, keyed by dotted paths.
+    ///
+    /// # Example
+    /// ```js
+    /// const patch = i18n.diff('en', await (await fetch('/en.json')).json());
+    /// // [{ op: "replace", path: "menu.open", value: "Open…", oldValue: "Open" }]
+    /// ```
+    pub fn diff(&self, locale: &str, target: JsValue) -> Result<JsValue, JsValue> {
+        let translations = self.translations.read().unwrap();
+        let source = translations.translations.get(locale).ok_or("Locale not found")?;
+        let ops = match target.as_string() {
+            Some(target) => diff_maps(source, translations.translations.get(&target).ok_or("Locale not found")?),
+            None => diff_maps(source, &serde_wasm_bindgen::from_value(target)?),
+        };
+        to_js_object(&ops)
+    }
+
+    /// Applies a patch produced by `diff` to `locale`, atomically like
+    /// `batch`: `add` and `replace` write their value, `remove` deletes a key
+    /// that must exist.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.applyPatch('en', await (await fetch('/en.patch.json')).json());
+    /// ```
+    #[wasm_bindgen(js_name = applyPatch)]
+    pub fn apply_patch(&self, locale: &str, patch: JsValue) -> Result<(), JsValue> {
+        self.apply_batch(patch_to_ops(locale, serde_wasm_bindgen::from_value(patch)?))
     }
 
     /// Formats a translation for a given locale, key, and arguments.
     ///
+    /// Accepts the same `{ context }` option as `getTranslation`, plus `mode`
+    /// and `missingMarker` to override the instance's format mode for this call
+    /// (see `setFormatMode`). `strict: true` is shorthand for `mode: "strict"`.
+    ///
+    /// With `escape: true` the result is safe to assign to `innerHTML`: string
+    /// arguments are HTML-escaped, and so is the template, except for tags
+    /// listed in `allowedTags` (by default simple formatting tags such as `b`,
+    /// `em` and `br`) written without attributes.
+    ///
+    /// Placeholders are substituted as `{name}`. With `icu: true` the string
+    /// is formatted as an ICU message instead, as `formatToParts` does.
+    ///
     /// # Example
     /// ```js
     /// let formatted = i18n.format_translation('en', 'greeting', { name: 'Alice' });
     /// console.log(formatted); // "Hello, Alice!"
+    /// i18n.formatTranslation('en', 'open', {}, { context: 'status' });
+    /// i18n.formatTranslation('en', 'greeting', {}, { mode: 'replace-missing' }); // "Hello, ⟦name⟧!"
+    /// i18n.formatTranslation('en', 'bold', { name: '<script>' }, { escape: true }); // "<b>&lt;script&gt;</b>"
     /// ```
     #[wasm_bindgen(js_name = formatTranslation)]
-    pub fn format_translation(&self, locale: &str, key: &str, args: JsValue) -> Result<String, JsValue> {
-        let translations = self.translations.read().unwrap();
-        let value = get_value_by_key(
-            translations
-                .translations
-                .get(locale)
-                .ok_or("Locale not found")?,
-            key,
-        )?;
+    pub fn format_translation_with_options(&self, locale: &str, key: &str, args: JsValue, options: JsValue) -> Result<String, JsValue> {
+        let options = TranslationOptions::from_js(options)?;
+        let text = self.format_message(locale, key, args, &options)?;
+        if !options.escape {
+            return Ok(text);
+        }
+        Ok(render_html(&parse_markup(&text, |tag| options.allows_tag(tag))))
+    }
 
-        let args_map: HashMap<String, String> = serde_wasm_bindgen::from_value(args)?;
-        match value {
-            TranslationValue::String(s) => format_string(s, &args_map).map_err(JsValue::from),
-            TranslationValue::Nested(_) => {
-                Err(JsValue::from("Translation is not a string".to_string()))
+    /// Formats a translation into a tree of text and tag nodes, so frameworks
+    /// can render rich text without `innerHTML`.
+    ///
+    /// Arguments are always treated as text. Tags written in the template are
+    /// returned as `{ type: "tag", name, children }` nodes when they are
+    /// allowed (see the `escape` option of `formatTranslation`); everything else
+    /// becomes `{ type: "text", value }`.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.setTranslations('en', { welcome: 'Hi <b>{name}</b>!' });
+    /// i18n.formatToTokens('en', 'welcome', { name: '<i>Ann</i>' });
+    /// // [{ type: "text", value: "Hi " },
+    /// //  { type: "tag", name: "b", children: [{ type: "text", value: "<i>Ann</i>" }] },
+    /// //  { type: "text", value: "!" }]
+    /// ```
+    #[wasm_bindgen(js_name = formatToTokens)]
+    pub fn format_to_tokens(&self, locale: &str, key: &str, args: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+        let mut options = TranslationOptions::from_js(options)?;
+        options.escape = true;
+        let text = self.format_message(locale, key, args, &options)?;
+        to_js_object(&parse_markup(&text, |tag| options.allows_tag(tag)))
+    }
+
+    /// Formats a translation into typed parts, like `Intl.*.formatToParts`.
+    ///
+    /// Each part is `{ type, value }`: `literal` text, a plain `argument`, a
+    /// `number` or `date` argument (with its ICU `style`, if any) and `plural`
+    /// for the `#` of a plural option. Argument parts carry the argument's
+    /// `name`. Joining the values gives the result of `formatTranslation`
+    /// with `{ icu: true }`. Fluent messages are returned as a single literal
+    /// part.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.setTranslations('en', { inbox: 'You have {count, plural, one {# new message} other {# new messages}}' });
+    /// i18n.formatToParts('en', 'inbox', { count: 5 });
+    /// // [{ type: "literal", value: "You have " },
+    /// //  { type: "plural", value: "5", name: "count" },
+    /// //  { type: "literal", value: " new messages" }]
+    /// ```
+    #[wasm_bindgen(js_name = formatToParts)]
+    pub fn format_to_parts(&self, locale: &str, key: &str, args: JsValue, options: JsValue) -> Result<JsValue, JsValue> {
+        let mut options = TranslationOptions::from_js(options)?;
+        options.icu = true;
+        to_js_object(&self.format_message_parts(locale, key, args, &options)?)
+    }
+
+    /// Formats a translation with rich-text tags rendered by callbacks.
+    ///
+    /// Every tag in the template whose name is a key of `tags` (`<link>...</link>`
+    /// or `<icon/>`) is replaced by the result of calling that function with the
+    /// array of its rendered children. The result is an array of strings and
+    /// callback results; arguments and all other markup are plain text.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.setTranslations('en', { terms: 'Read the <link>terms</link> and <b>agree</b>' });
+    /// const parts = i18n.formatRich('en', 'terms', {}, {
+    ///     link: (children) => h('a', { href: '/terms' }, children),
+    ///     b: (children) => h('strong', {}, children),
+    /// });
+    /// // ["Read the ", <a>, " and ", <strong>]
+    /// ```
+    #[wasm_bindgen(js_name = formatRich)]
+    pub fn format_rich(&self, locale: &str, key: &str, args: JsValue, tags: JsValue, options: JsValue) -> Result<js_sys::Array, JsValue> {
+        let mut options = TranslationOptions::from_js(options)?;
+        options.escape = true;
+        let tags: js_sys::Object = tags.dyn_into().map_err(|_| JsValue::from("Tags must be an object"))?;
+        let mut callbacks = HashMap::new();
+        for entry in js_sys::Object::entries(&tags).iter() {
+            let entry: js_sys::Array = entry.into();
+            if let (Some(name), Ok(callback)) = (entry.get(0).as_string(), entry.get(1).dyn_into::<js_sys::Function>()) {
+                callbacks.insert(name, callback);
             }
         }
+
+        // The lock is released before any callback runs, so callbacks may use
+        // this instance again.
+        let text = self.format_message(locale, key, args, &options)?;
+        render_rich(&parse_markup(&text, |tag| callbacks.contains_key(tag)), &callbacks)
     }
 
     /// Retrieves all translations for a specific locale.
@@ -341,10 +1115,1235 @@ impl I18n {
     #[wasm_bindgen(js_name = hasKeyInTranslations)]
     pub fn has_key_in_translations(&self, locale: &str, key: &str) -> bool {
         let translations = self.translations.read().unwrap();
+        if translations.indexed(locale, key).is_some() {
+            return true;
+        }
         if let Some(translation_map) = translations.translations.get(locale) {
             get_value_by_key(translation_map, key).is_ok()
         } else {
             false
         }
     }
-}
\ No newline at end of file
+
+    /// Enables or disables the flattened key index.
+    ///
+    /// While enabled, every string leaf is mirrored in a `dotted.path → string`
+    /// map so `getTranslation`, `hasTranslation` and `formatTranslation` resolve
+    /// with a single hash lookup instead of walking the nested tree.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.setKeyIndexEnabled(true);
+    /// ```
+    #[wasm_bindgen(js_name = setKeyIndexEnabled)]
+    pub fn set_key_index_enabled(&self, enabled: bool) {
+        let mut translations = self.translations.write().unwrap();
+        if !enabled {
+            translations.index = None;
+            return;
+        }
+        if translations.index.is_some() {
+            return;
+        }
+
+        let mut index = KeyIndex::default();
+        for (locale, map) in &translations.translations {
+            index.rebuild_locale(locale, map);
+        }
+        translations.index = Some(index);
+    }
+
+    /// Checks whether the flattened key index is enabled.
+    ///
+    /// # Example
+    /// ```js
+    /// console.log(i18n.keyIndexEnabled); // true or false
+    /// ```
+    #[wasm_bindgen(getter, js_name = keyIndexEnabled)]
+    pub fn key_index_enabled(&self) -> bool {
+        self.translations.read().unwrap().index.is_some()
+    }
+
+    /// Sets how `formatTranslation` treats arguments that do not match the
+    /// message: `"strict"` fails on a missing or unused argument, `"lenient"`
+    /// (the default) leaves `{name}` in place and ignores extra arguments, and
+    /// `"replace-missing"` substitutes `missingMarker` for absent arguments.
+    /// `{name}` in the marker is replaced by the argument name; it defaults to
+    /// `⟦{name}⟧`. Per-call `mode` and `missingMarker` options take precedence.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.setFormatMode(import.meta.env.DEV ? 'strict' : 'replace-missing');
+    /// i18n.setFormatMode('replace-missing', '[{name}]');
+    /// ```
+    #[wasm_bindgen(js_name = setFormatMode)]
+    pub fn set_format_mode(&self, mode: &str, missing_marker: Option<String>) -> Result<(), JsValue> {
+        let mode = FormatMode::parse(mode)?;
+        let mut translations = self.translations.write().unwrap();
+        translations.format_mode = mode;
+        translations.missing_marker = missing_marker;
+        Ok(())
+    }
+
+    /// Returns the instance's format mode.
+    ///
+    /// # Example
+    /// ```js
+    /// console.log(i18n.formatMode); // "lenient"
+    /// ```
+    #[wasm_bindgen(getter, js_name = formatMode)]
+    pub fn format_mode(&self) -> String {
+        self.translations.read().unwrap().format_mode.as_str().to_string()
+    }
+
+    /// Subscribes to an event and returns a handle whose `unsubscribe()`
+    /// removes the listener.
+    ///
+    /// - `change` fires once per affected locale after `setTranslations`,
+    ///   `updateTranslation`, `delTranslation`, `delTranslations`,
+    ///   `clearAllTranslations`, `loadTranslations`, `loadBundle` and the
+    ///   `import*` methods, with `{ locale, keys }` listing the dotted keys of
+    ///   the strings written or removed.
+    /// - `localeChanged` fires when `setLocale` switches the current locale,
+    ///   with `{ locale, previous }`.
+    /// - `loaded` fires when `loadTranslations` or `loadBundle` completes, with
+    ///   `{ locales, url? }`.
+    ///
+    /// Listeners run after the instance's lock is released and may read from
+    /// it. A listener that throws is reported on the console.
+    ///
+    /// # Example
+    /// ```js
+    /// const subscription = i18n.on('change', ({ locale, keys }) => rerender(locale, keys));
+    /// subscription.unsubscribe();
+    /// ```
+    pub fn on(&self, event: &str, callback: js_sys::Function) -> Result<Subscription, JsValue> {
+        let event = Event::parse(event)?;
+        if event == Event::Change {
+            self.translations.write().unwrap().track_changes = true;
+        }
+        let id = self.listeners.borrow_mut().add(event, callback);
+        Ok(Subscription::new(&self.listeners, id))
+    }
+
+    /// Sets the current locale, emitting `localeChanged` when it changes. The
+    /// locale does not need to have translations yet.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.on('localeChanged', ({ locale }) => document.documentElement.lang = locale);
+    /// i18n.setLocale('de');
+    /// ```
+    #[wasm_bindgen(js_name = setLocale)]
+    pub fn set_locale(&self, locale: &str) {
+        let previous = self.translations.write().unwrap().locale.replace(locale.to_string());
+        if previous.as_deref() != Some(locale) {
+            self.emit(Event::LocaleChanged, &LocaleChangedEvent { locale: locale.to_string(), previous });
+        }
+    }
+
+    /// Returns the current locale set with `setLocale`, if any.
+    ///
+    /// # Example
+    /// ```js
+    /// console.log(i18n.locale); // "de"
+    /// ```
+    #[wasm_bindgen(getter)]
+    pub fn locale(&self) -> Option<String> {
+        self.translations.read().unwrap().locale.clone()
+    }
+
+    /// Enables or disables the undo/redo history. While enabled, every
+    /// mutation is recorded with the previous values of the keys it touched,
+    /// keeping at most `depth` steps (100 by default). Enabling it again only
+    /// changes the depth; disabling drops the recorded history.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.setHistoryEnabled(true, 50);
+    /// ```
+    #[wasm_bindgen(js_name = setHistoryEnabled)]
+    pub fn set_history_enabled(&self, enabled: bool, depth: Option<usize>) {
+        let depth = depth.unwrap_or(DEFAULT_HISTORY_DEPTH);
+        let mut translations = self.translations.write().unwrap();
+        match translations.history.as_mut() {
+            Some(history) if enabled => history.set_depth(depth),
+            _ => translations.history = enabled.then(|| History::new(depth)),
+        }
+    }
+
+    /// Reverts the last recorded mutation. Returns `false` when there is
+    /// nothing to undo.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.updateTranslation('en', 'hello', 'Hi');
+    /// i18n.undo(); // "hello" is back to its previous value
+    /// ```
+    pub fn undo(&self) -> Result<bool, JsValue> {
+        self.replay(true)
+    }
+
+    /// Re-applies the last undone mutation. Returns `false` when there is
+    /// nothing to redo; any new mutation clears the redo stack.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.redo();
+    /// ```
+    pub fn redo(&self) -> Result<bool, JsValue> {
+        self.replay(false)
+    }
+
+    /// Whether `undo()` has a step to revert.
+    ///
+    /// # Example
+    /// ```js
+    /// undoButton.disabled = !i18n.canUndo;
+    /// ```
+    #[wasm_bindgen(getter, js_name = canUndo)]
+    pub fn can_undo(&self) -> bool {
+        self.translations.read().unwrap().history.as_ref().is_some_and(History::can_undo)
+    }
+
+    /// Whether `redo()` has a step to re-apply.
+    ///
+    /// # Example
+    /// ```js
+    /// redoButton.disabled = !i18n.canRedo;
+    /// ```
+    #[wasm_bindgen(getter, js_name = canRedo)]
+    pub fn can_redo(&self) -> bool {
+        self.translations.read().unwrap().history.as_ref().is_some_and(History::can_redo)
+    }
+
+    /// Exports the net effect of the undoable history as a list of
+    /// `{ op: "set", locale, key, value }` and `{ op: "delete", locale, key }`
+    /// operations, one per edited key, which `batch()` can apply elsewhere.
+    ///
+    /// # Example
+    /// ```js
+    /// const patch = editor.exportHistory();
+    /// await fetch('/translations/patch', { method: 'POST', body: JSON.stringify(patch) });
+    /// production.batch(patch);
+    /// ```
+    #[wasm_bindgen(js_name = exportHistory)]
+    pub fn export_history(&self) -> Result<JsValue, JsValue> {
+        let translations = self.translations.read().unwrap();
+        let patch = translations.history.as_ref().map(History::patch).unwrap_or_default();
+        to_js_object(&patch)
+    }
+
+    /// Forgets all recorded steps, keeping the history enabled.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.clearHistory();
+    /// ```
+    #[wasm_bindgen(js_name = clearHistory)]
+    pub fn clear_history(&self) {
+        let mut translations = self.translations.write().unwrap();
+        if let Some(history) = translations.history.as_mut() {
+            *history = History::new(history.depth());
+        }
+    }
+
+    /// Loads translations from a binary bundle produced by `encodeBundle`.
+    ///
+    /// Accepts an `ArrayBuffer` or `Uint8Array` and deep-merges its locales
+    /// into the existing translations, creating locales as needed. The buffer
+    /// is copied into wasm memory once and decoded into complete translation
+    /// trees before anything is written, so a corrupt bundle changes nothing.
+    ///
+    /// # Example
+    /// ```js
+    /// const resp = await fetch('/translations.bin');
+    /// i18n.loadBundle(await resp.arrayBuffer());
+    /// ```
+    #[wasm_bindgen(js_name = loadBundle)]
+    pub fn load_bundle(&self, data: JsValue) -> Result<(), JsValue> {
+        let bytes = binary_bytes(&data)?;
+        let locales = decode_bundle(&bytes)?;
+        drop(bytes);
+
+        let mut translations = self.mutate();
+        let mut loaded = Vec::with_capacity(locales.len());
+        for (locale, map) in locales {
+            for key in map.keys() {
+                let path = escape_segment(key);
+                translations.touch(&locale, &path);
+                translations.forget_fluent(&locale, &path);
+            }
+            match translations.translations.get_mut(&locale) {
+                Some(existing) => {
+                    for (key, value) in map {
+                        merge_entry(existing, key, value, MergeStrategy::Deep);
+                    }
+                }
+                None => {
+                    translations.translations.insert(locale.clone(), map);
+                }
+            }
+            let Translations { index, translations: locales, .. } = &mut *translations;
+            if let Some(index) = index.as_mut() {
+                index.rebuild_locale(&locale, &locales[&locale]);
+            }
+            loaded.push(locale);
+        }
+        self.commit(translations);
+        self.emit(Event::Loaded, &LoadedEvent { locales: loaded, url: None });
+        Ok(())
+    }
+
+    /// Exports the full state of the instance (all locales and settings) as a
+    /// versioned JSON snapshot, suitable for IndexedDB or embedding in SSR HTML.
+    ///
+    /// # Example
+    /// ```js
+    /// const json = i18n.toSnapshot();
+    /// const restored = I18n.fromSnapshot(json);
+    /// ```
+    #[wasm_bindgen(js_name = toSnapshot)]
+    pub fn to_snapshot(&self) -> Result<String, JsValue> {
+        serde_json::to_string(&self.snapshot())
+            .map_err(|e| JsValue::from(format!("Failed to serialize snapshot: {}", e)))
+    }
+
+    /// Exports the full state of the instance as a compact binary snapshot.
+    ///
+    /// # Example
+    /// ```js
+    /// const bytes = i18n.toSnapshotBinary();
+    /// const restored = I18n.fromSnapshot(bytes);
+    /// ```
+    #[wasm_bindgen(js_name = toSnapshotBinary)]
+    pub fn to_snapshot_binary(&self) -> Vec<u8> {
+        self.snapshot().to_binary()
+    }
+
+    /// Restores an instance from a snapshot produced by `toSnapshot` (a string)
+    /// or `toSnapshotBinary` (an `ArrayBuffer` or `Uint8Array`).
+    ///
+    /// # Example
+    /// ```js
+    /// const i18n = I18n.fromSnapshot(localStorage.getItem('i18n'));
+    /// ```
+    #[wasm_bindgen(js_name = fromSnapshot)]
+    pub fn from_snapshot(data: JsValue) -> Result<I18n, JsValue> {
+        let i18n = I18n::new();
+
+        let settings = if let Some(json) = data.as_string() {
+            let snapshot = Snapshot::from_json(&json)?;
+            let mut translations = i18n.translations.write().unwrap();
+            translations.translations = snapshot.translations;
+            translations.metadata = snapshot.metadata;
+            snapshot.settings
+        } else {
+            let bytes = binary_bytes(&data)?;
+            let (settings, metadata, locales) = decode_snapshot_binary(&bytes)?;
+            let mut translations = i18n.translations.write().unwrap();
+            translations.metadata = metadata;
+            translations.translations = locales.into_iter().collect();
+            settings
+        };
+
+        i18n.apply_settings(&settings);
+        Ok(i18n)
+    }
+
+    /// Imports a gettext `.po` file into a locale.
+    ///
+    /// Each `msgid` becomes a dotted key, `msgctxt` is stored as `key#context`
+    /// and plural messages become `{ "0": ..., "1": ... }` maps. Fuzzy entries
+    /// are skipped unless `includeFuzzy` is `true`. Returns a report with the
+    /// number of imported entries and the `Plural-Forms` header.
+    ///
+    /// # Example
+    /// ```js
+    /// const report = i18n.importPo('de', poText);
+    /// console.log(report); // { imported: 42, skippedFuzzy: 1, pluralForms: "nplurals=2; plural=(n != 1);" }
+    /// ```
+    #[wasm_bindgen(js_name = importPo)]
+    pub fn import_po(&self, locale: &str, text: &str, include_fuzzy: Option<bool>) -> Result<JsValue, JsValue> {
+        let entries = parse_po(text)?;
+        let report = self.import_gettext(locale, &entries, include_fuzzy.unwrap_or(false))?;
+        serde_wasm_bindgen::to_value(&report).map_err(JsValue::from)
+    }
+
+    /// Imports a compiled gettext `.mo` file (`ArrayBuffer` or `Uint8Array`) into a locale.
+    ///
+    /// # Example
+    /// ```js
+    /// const resp = await fetch('/locales/de.mo');
+    /// i18n.importMo('de', await resp.arrayBuffer());
+    /// ```
+    #[wasm_bindgen(js_name = importMo)]
+    pub fn import_mo(&self, locale: &str, data: JsValue) -> Result<JsValue, JsValue> {
+        let bytes = binary_bytes(&data)?;
+        let entries = parse_mo(&bytes)?;
+        let report = self.import_gettext(locale, &entries, false)?;
+        serde_wasm_bindgen::to_value(&report).map_err(JsValue::from)
+    }
+
+    /// Exports a locale as a gettext `.po` file.
+    ///
+    /// # Example
+    /// ```js
+    /// const po = i18n.exportPo('de', 'nplurals=2; plural=(n != 1);');
+    /// ```
+    #[wasm_bindgen(js_name = exportPo)]
+    pub fn export_po(&self, locale: &str, plural_forms: Option<String>) -> Result<String, JsValue> {
+        let plural_forms = plural_forms.as_deref().unwrap_or(DEFAULT_PLURAL_FORMS);
+        let plural = PluralForms::parse(plural_forms)?;
+        let translations = self.translations.read().unwrap();
+        let map = translations
+            .translations
+            .get(locale)
+            .ok_or("Locale not found")?;
+        let categories = if contains_plural_map(map) {
+            plural.locale_categories(locale)?
+        } else {
+            Vec::new()
+        };
+        Ok(write_po(locale, map, translations.metadata.get(locale), plural_forms, &categories))
+    }
+
+    /// Imports a Project Fluent (`.ftl`) resource into a locale.
+    ///
+    /// Messages are stored under their identifier, terms under `-identifier`
+    /// and attributes as `message.attribute`. `formatTranslation` evaluates
+    /// the imported messages as Fluent patterns, including select expressions,
+    /// term and message references; other keys of the locale keep their usual
+    /// formatting. A message stays Fluent until it is replaced or removed by
+    /// something other than a Fluent import.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.importFtl('en', `
+    /// -brand = Firefox
+    /// emails = { $count ->
+    ///     [one] You have one email in { -brand }.
+    ///    *[other] You have { $count } emails in { -brand }.
+    /// }`);
+    /// i18n.formatTranslation('en', 'emails', { count: 5 }); // "You have 5 emails in Firefox."
+    /// ```
+    #[wasm_bindgen(js_name = importFtl)]
+    pub fn import_ftl(&self, locale: &str, text: &str) -> Result<(), JsValue> {
+        let entries = parse_resource(text)?;
+
+        let mut translations = self.mutate();
+        translations.locale_mut(locale);
+        for (key, value) in entries {
+            translations.set_entry(locale, key.clone(), value);
+            translations.fluent_keys.entry(locale.to_string()).or_default().insert(key);
+        }
+        self.commit(translations);
+        Ok(())
+    }
+
+    /// Imports the units of an XLIFF 1.2 or 2.0 document into its target locale.
+    ///
+    /// Unit ids are used as dotted keys. The target locale comes from the
+    /// document (`target-language` / `trgLang`) unless `locale` is given. With
+    /// `includeSource`, source texts are imported into the source locale too.
+    /// Returns a report with import counts, unit states and notes per key.
+    ///
+    /// # Example
+    /// ```js
+    /// const report = i18n.importXliff(xliffText);
+    /// console.log(report); // { imported: 10, skipped: 2, targetLocale: "de", states: { translated: 10 }, ... }
+    /// ```
+    #[wasm_bindgen(js_name = importXliff)]
+    pub fn import_xliff(&self, text: &str, locale: Option<String>, include_source: Option<bool>) -> Result<JsValue, JsValue> {
+        let document = parse_xliff(text)?;
+        let target_locale = locale
+            .or(document.target_locale)
+            .ok_or("XLIFF document has no target language")?;
+        let mut report = XliffImportReport {
+            source_locale: document.source_locale,
+            target_locale: target_locale.clone(),
+            ..XliffImportReport::default()
+        };
+
+        let source_locale = report.source_locale.clone().filter(|_| include_source.unwrap_or(false));
+        let mut translations = self.mutate();
+
+        // Write into copies of the locales so that a unit whose key clashes
+        // with the existing tree fails the import before anything changes.
+        let mut target_map = translations.translations.get(&target_locale).cloned().unwrap_or_default();
+        let mut source_map = source_locale
+            .as_ref()
+            .map(|locale| translations.translations.get(locale).cloned().unwrap_or_default());
+        let mut written = Vec::with_capacity(document.units.len());
+        report.skipped = document.unnamed;
+        for unit in document.units {
+            let keys = split_key(&unit.id);
+            let error = |e: String| format!("Unit '{}': {}", unit.id, e);
+            if let Some(source_map) = source_map.as_mut() {
+                update_translation_recursive(source_map, &keys, TranslationValue::String(unit.source.clone())).map_err(error)?;
+            }
+            let translated = match &unit.target {
+                Some(target) if !target.is_empty() => {
+                    update_translation_recursive(&mut target_map, &keys, TranslationValue::String(target.clone()))
+                        .map_err(error)?;
+                    report.imported += 1;
+                    true
+                }
+                _ => {
+                    report.skipped += 1;
+                    false
+                }
+            };
+            if let Some(state) = &unit.state {
+                *report.states.entry(state.clone()).or_default() += 1;
+            }
+            if !unit.notes.is_empty() {
+                report.notes.insert(unit.id.clone(), unit.notes.clone());
+            }
+            written.push((unit.metadata(), unit.id, translated));
+        }
+
+        let mut locales = vec![(target_locale.clone(), target_map, true)];
+        locales.extend(source_locale.zip(source_map).map(|(locale, map)| (locale, map, false)));
+        for (locale, map, is_target) in locales {
+            for (_, key, translated) in &written {
+                if *translated || !is_target {
+                    translations.touch(&locale, key);
+                }
+            }
+            if let Some(index) = translations.index.as_mut() {
+                index.rebuild_locale(&locale, &map);
+            }
+            translations.translations.insert(locale.clone(), map);
+            for (_, key, translated) in &written {
+                if *translated || !is_target {
+                    translations.forget_fluent(&locale, key);
+                }
+            }
+        }
+        for (metadata, key, _) in written {
+            translations.add_metadata(&target_locale, &key, metadata);
+        }
+        self.commit(translations);
+        serde_wasm_bindgen::to_value(&report).map_err(JsValue::from)
+    }
+
+    /// Exports a source/target locale pair as an XLIFF document (`"1.2"` by
+    /// default, or `"2.0"`). Every string in the source locale becomes a unit
+    /// whose id is its dotted key.
+    ///
+    /// # Example
+    /// ```js
+    /// const xliff = i18n.exportXliff('en', 'de', '2.0');
+    /// ```
+    #[wasm_bindgen(js_name = exportXliff)]
+    pub fn export_xliff(&self, source_locale: &str, target_locale: &str, version: Option<String>) -> Result<String, JsValue> {
+        let translations = self.translations.read().unwrap();
+        let source = translations
+            .translations
+            .get(source_locale)
+            .ok_or("Locale not found")?;
+        let target = translations.translations.get(target_locale);
+        // Per key, the target's metadata wins and the source's fills the gaps.
+        let mut metadata = translations.metadata.get(source_locale).cloned().unwrap_or_default();
+        for (key, target_metadata) in translations.metadata.get(target_locale).into_iter().flatten() {
+            metadata.entry(key.clone()).or_default().merge(target_metadata.clone());
+        }
+        write_xliff(version.as_deref().unwrap_or("1.2"), source_locale, target_locale, source, target, Some(&metadata))
+            .map_err(JsValue::from)
+    }
+
+    /// Imports an Android `strings.xml` resource file into a locale.
+    ///
+    /// `<string>` entries become strings, `<plurals>` become maps keyed by
+    /// plural category and `<string-array>` items become maps keyed by index.
+    /// printf placeholders are converted by position: `%1$s` becomes `{1}`.
+    /// Styling tags such as `<b>` are kept for `formatRich`; other inline
+    /// elements such as `<xliff:g>` are replaced by their text.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.importAndroidStrings('en', `<resources>
+    ///     <string name="welcome">Hello, %1$s!</string>
+    /// </resources>`);
+    /// i18n.formatTranslation('en', 'welcome', { 1: 'Ann' }); // "Hello, Ann!"
+    /// ```
+    #[wasm_bindgen(js_name = importAndroidStrings)]
+    pub fn import_android_strings(&self, locale: &str, xml: &str) -> Result<(), JsValue> {
+        let entries = parse_android(xml)?;
+        self.import_entries(locale, entries)
+    }
+
+    /// Exports a locale as an Android `strings.xml` resource file. Plural maps
+    /// become `<plurals>`, index maps become `<string-array>` and `{name}`
+    /// placeholders become positional `%N$s` placeholders.
+    ///
+    /// # Example
+    /// ```js
+    /// const xml = i18n.exportAndroidStrings('en');
+    /// ```
+    #[wasm_bindgen(js_name = exportAndroidStrings)]
+    pub fn export_android_strings(&self, locale: &str) -> Result<String, JsValue> {
+        let translations = self.translations.read().unwrap();
+        let map = translations
+            .translations
+            .get(locale)
+            .ok_or("Locale not found")?;
+        Ok(write_android(map))
+    }
+
+    /// Imports an Apple `.strings` file into a locale. printf placeholders are
+    /// converted by position: `%@` and `%1$@` become `{1}`. Keys are dotted
+    /// paths, except that sentences such as `"Loading..."` are single keys.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.importAppleStrings('en', '"welcome" = "Hello, %@!";');
+    /// ```
+    #[wasm_bindgen(js_name = importAppleStrings)]
+    pub fn import_apple_strings(&self, locale: &str, text: &str) -> Result<(), JsValue> {
+        let entries = parse_apple_strings(text)?
+            .into_iter()
+            .map(|(key, value)| (key, TranslationValue::String(value)))
+            .collect();
+        self.import_entries(locale, entries)
+    }
+
+    /// Exports the strings of a locale as an Apple `.strings` file. Plural maps
+    /// are left out; export them with `exportAppleStringsdict`.
+    ///
+    /// # Example
+    /// ```js
+    /// const strings = i18n.exportAppleStrings('en');
+    /// ```
+    #[wasm_bindgen(js_name = exportAppleStrings)]
+    pub fn export_apple_strings(&self, locale: &str) -> Result<String, JsValue> {
+        let translations = self.translations.read().unwrap();
+        let map = translations
+            .translations
+            .get(locale)
+            .ok_or("Locale not found")?;
+        Ok(write_apple_strings(map))
+    }
+
+    /// Imports an Apple `.stringsdict` file into a locale. Each entry becomes a
+    /// map keyed by plural category, with the `%#@variable@` references of its
+    /// format string already substituted.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.importAppleStringsdict('en', stringsdictXml);
+    /// i18n.getTranslation('en', 'emails'); // { one: "{1} email", other: "{1} emails" }
+    /// ```
+    #[wasm_bindgen(js_name = importAppleStringsdict)]
+    pub fn import_apple_stringsdict(&self, locale: &str, xml: &str) -> Result<(), JsValue> {
+        let entries = parse_stringsdict(xml)?;
+        self.import_entries(locale, entries)
+    }
+
+    /// Exports the plural maps of a locale as an Apple `.stringsdict` file.
+    ///
+    /// # Example
+    /// ```js
+    /// const stringsdict = i18n.exportAppleStringsdict('en');
+    /// ```
+    #[wasm_bindgen(js_name = exportAppleStringsdict)]
+    pub fn export_apple_stringsdict(&self, locale: &str) -> Result<String, JsValue> {
+        let translations = self.translations.read().unwrap();
+        let map = translations
+            .translations
+            .get(locale)
+            .ok_or("Locale not found")?;
+        Ok(write_stringsdict(map))
+    }
+
+    /// Imports a Flutter ARB (`.arb`) document into a locale.
+    ///
+    /// The locale is taken from `locale` or else from the document's
+    /// `@@locale`. `@key` metadata entries (descriptions, placeholder docs) are
+    /// kept alongside the translations instead of being imported as messages,
+    /// and written back by `exportArb`. Returns the locale that was imported.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.importArb(`{
+    ///   "@@locale": "en",
+    ///   "greeting": "Hello {name}",
+    ///   "@greeting": { "description": "Home screen greeting" }
+    /// }`); // "en"
+    /// ```
+    #[wasm_bindgen(js_name = importArb)]
+    pub fn import_arb(&self, text: &str, locale: Option<String>) -> Result<String, JsValue> {
+        let document = parse_arb(text)?;
+        let locale = locale
+            .or(document.locale)
+            .ok_or("ARB document has no @@locale")?;
+
+        // Write into a copy so a conflicting message changes nothing.
+        let mut translations = self.mutate();
+        let mut map = translations.translations.get(&locale).cloned().unwrap_or_default();
+        let mut keys = Vec::with_capacity(document.messages.len());
+        for (key, text) in document.messages {
+            update_translation_recursive(&mut map, &split_key(&key), TranslationValue::String(text))
+                .map_err(|error| format!("Message '{}': {}", key, error))?;
+            keys.push(key);
+        }
+        translations.swap_locale(&locale, map, &keys);
+        for (key, metadata) in document.metadata {
+            translations.add_metadata(&locale, &key, metadata);
+        }
+        self.commit(translations);
+        Ok(locale)
+    }
+
+    /// Exports a locale as a Flutter ARB document, including any `@key`
+    /// metadata and `@@` attributes imported with it.
+    ///
+    /// # Example
+    /// ```js
+    /// const arb = i18n.exportArb('en');
+    /// ```
+    #[wasm_bindgen(js_name = exportArb)]
+    pub fn export_arb(&self, locale: &str) -> Result<String, JsValue> {
+        let translations = self.translations.read().unwrap();
+        let map = translations
+            .translations
+            .get(locale)
+            .ok_or("Locale not found")?;
+        Ok(write_arb(locale, map, translations.metadata.get(locale)))
+    }
+
+    /// Validates every locale against `sourceLocale`.
+    ///
+    /// The report lists, per locale, keys missing from it, orphan keys absent
+    /// from the source, keys that are a string in one locale and nested in the
+    /// other, placeholder differences (e.g. `{username}` used in the source but
+    /// not in the translation) and ICU MessageFormat syntax errors. Plural maps
+    /// are compared as a whole. Fluent locales skip the syntax checks.
+    ///
+    /// # Example
+    /// ```js
+    /// const report = i18n.validate('en');
+    /// if (!report.valid) {
+    ///     console.log(report.locales.de.missing); // ["menu.close"]
+    ///     console.log(report.locales.de.placeholderMismatches); // [{ key: "welcome", missing: ["username"], extra: [] }]
+    /// }
+    /// ```
+    #[wasm_bindgen(js_name = validate)]
+    pub fn validate(&self, source_locale: &str) -> Result<JsValue, JsValue> {
+        let translations = self.translations.read().unwrap();
+        let report = validate_locales(source_locale, &translations.translations, |locale, key| {
+            translations.fluent_keys.get(locale).is_some_and(|keys| keys.contains(key))
+        })?;
+        to_js_object(&report)
+    }
+
+    /// Reports how complete every locale is compared to `sourceLocale`.
+    ///
+    /// For each locale, and for each top-level key of the source within it,
+    /// the report gives the number and percentage of source keys translated,
+    /// the word count of the source strings and of those still untranslated,
+    /// and the untranslated keys. Plural maps count as one key, and empty
+    /// strings as untranslated.
+    ///
+    /// # Example
+    /// ```js
+    /// const report = i18n.coverage('en');
+    /// console.log(report.locales.pl.percent); // 87.5
+    /// console.log(report.locales.pl.namespaces.checkout.untranslated); // ["checkout.total"]
+    /// ```
+    #[wasm_bindgen(js_name = coverage)]
+    pub fn coverage(&self, source_locale: &str) -> Result<JsValue, JsValue> {
+        let translations = self.translations.read().unwrap();
+        to_js_object(&coverage(source_locale, &translations.translations)?)
+    }
+
+    /// Lists the arguments a message expects, in order of first appearance,
+    /// with their declared type: `plain`, `number`, `date`, `plural` or
+    /// `select`. Types come from ICU arguments (`{n, plural, ...}`) or, in
+    /// Fluent locales, from `NUMBER()`/`DATETIME()` calls and select
+    /// expressions. Plural maps report the union over their forms. Accepts the
+    /// same `{ context }` option as `getTranslation`.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.setTranslations('en', { cart: '{user} has {count, plural, one {# item} other {# items}}' });
+    /// i18n.getPlaceholders('en', 'cart'); // [{ name: "user", type: "plain" }, { name: "count", type: "plural" }]
+    /// ```
+    #[wasm_bindgen(js_name = getPlaceholders)]
+    pub fn get_placeholders(&self, locale: &str, key: &str, options: JsValue) -> Result<JsValue, JsValue> {
+        let options = TranslationOptions::from_js(options)?;
+        let translations = self.translations.read().unwrap();
+        let key = translations.resolve_key(locale, key, options.context.as_deref());
+        let map = translations
+            .translations
+            .get(locale)
+            .ok_or("Locale not found")?;
+        let value = get_value_by_key(map, &key)?;
+        let placeholders = placeholders(value, translations.is_fluent(locale, &key))?;
+        to_js_object(&placeholders)
+    }
+
+    /// Returns the metadata stored for a key, or `undefined` when it has none.
+    ///
+    /// Metadata holds translator-facing information such as `description`,
+    /// `context`, `maxLength`, `placeholders` and `lastModified`. It is filled
+    /// by `setMetadata` and by the ARB, XLIFF and gettext importers, and never
+    /// changes what `getTranslation` returns.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.getMetadata('en', 'greeting'); // { description: "Home screen greeting", maxLength: 40 }
+    /// ```
+    #[wasm_bindgen(js_name = getMetadata)]
+    pub fn get_metadata(&self, locale: &str, key: &str) -> Result<JsValue, JsValue> {
+        let translations = self.translations.read().unwrap();
+        let key = canonical_key(key);
+        match translations.metadata.get(locale).and_then(|metadata| metadata.get(key.as_ref())) {
+            Some(metadata) => to_js_object(metadata),
+            None => Ok(JsValue::UNDEFINED),
+        }
+    }
+
+    /// Replaces the metadata of a key. Passing `null`, `undefined` or an empty
+    /// object removes it.
+    ///
+    /// # Example
+    /// ```js
+    /// i18n.setMetadata('en', 'greeting', {
+    ///     description: 'Home screen greeting',
+    ///     maxLength: 40,
+    ///     placeholders: { name: { type: 'String', example: 'Bob' } },
+    /// });
+    /// ```
+    #[wasm_bindgen(js_name = setMetadata)]
+    pub fn set_metadata(&self, locale: &str, key: &str, metadata: JsValue) -> Result<(), JsValue> {
+        let metadata: KeyMetadata = if metadata.is_null() || metadata.is_undefined() {
+            KeyMetadata::default()
+        } else {
+            serde_wasm_bindgen::from_value(metadata)?
+        };
+
+        let key = canonical_key(key);
+        let mut translations = self.mutate();
+        if !translations.translations.contains_key(locale) {
+            return Err(JsValue::from("Locale not found"));
+        }
+        translations.touch(locale, &key);
+        let locale_metadata = translations.metadata.entry(locale.to_string()).or_default();
+        if metadata.is_empty() {
+            locale_metadata.remove(key.as_ref());
+        } else {
+            locale_metadata.insert(key.into_owned(), metadata);
+        }
+        self.commit(translations);
+        Ok(())
+    }
+}
+
+// Rust callers get the option-less signatures; JavaScript reaches the same
+// methods with optional trailing arguments.
+impl I18n {
+    /// Sets translations for a given locale with the default deep merge.
+    pub fn set_translations(&self, locale: &str, obj: JsValue) -> Result<(), JsValue> {
+        self.set_translations_with_options(locale, obj, JsValue::UNDEFINED)
+    }
+
+    /// Deletes a translation for a given key and locale, keeping parent
+    /// objects the removal leaves empty.
+    pub fn del_translation(&self, locale: &str, key: &str) -> Result<bool, JsValue> {
+        self.del_translation_with_options(locale, key, None)
+    }
+
+    /// Loads translations from a remote URL, detecting the format and reading
+    /// the document as keyed by locale.
+    pub async fn load_translations(&self, url: &str) -> Result<(), JsValue> {
+        self.load_translations_with_options(url, None, None).await
+    }
+
+    /// Gets a translation for a given key and locale, without options.
+    pub fn get_translation(&self, locale: &str, key: &str) -> Result<JsValue, JsValue> {
+        self.get_translation_with_options(locale, key, JsValue::UNDEFINED)
+    }
+
+    /// Formats a translation for a given locale, key, and arguments, without
+    /// options.
+    pub fn format_translation(&self, locale: &str, key: &str, args: JsValue) -> Result<String, JsValue> {
+        self.format_translation_with_options(locale, key, args, JsValue::UNDEFINED)
+    }
+}
+
+impl I18n {
+    /// Writes resources under the keys their names stand for (see
+    /// [`resource_key`]), into a copy of the locale so a conflicting file
+    /// changes nothing.
+    fn import_entries(&self, locale: &str, entries: Vec<(String, TranslationValue)>) -> Result<(), JsValue> {
+        let mut translations = self.mutate();
+        let mut map = translations.translations.get(locale).cloned().unwrap_or_default();
+        let mut keys = Vec::with_capacity(entries.len());
+        for (name, value) in entries {
+            let key = resource_key(&name).into_owned();
+            update_translation_recursive(&mut map, &split_key(&key), value)
+                .map_err(|error| format!("Resource '{}': {}", name, error))?;
+            keys.push(key);
+        }
+
+        translations.swap_locale(locale, map, &keys);
+        self.commit(translations);
+        Ok(())
+    }
+
+    fn import_gettext(&self, locale: &str, entries: &[PoEntry], include_fuzzy: bool) -> Result<ImportReport, JsValue> {
+        let mut report = ImportReport {
+            plural_forms: header_field(entries, "Plural-Forms"),
+            ..ImportReport::default()
+        };
+        let categories = if entries.iter().any(|entry| entry.id_plural.is_some()) {
+            let plural_forms = PluralForms::parse(report.plural_forms.as_deref().unwrap_or(DEFAULT_PLURAL_FORMS))?;
+            plural_forms.locale_categories(locale)?
+        } else {
+            Vec::new()
+        };
+
+        // Build every entry first; writing them cannot fail.
+        let mut imported = Vec::new();
+        for entry in entries {
+            if entry.is_header() || !entry.is_translated() {
+                continue;
+            }
+            if entry.fuzzy && !include_fuzzy {
+                report.skipped_fuzzy += 1;
+                continue;
+            }
+            imported.push((entry.key(), entry.value(&categories), entry.metadata()));
+        }
+        report.imported = imported.len();
+
+        let mut translations = self.mutate();
+        translations.locale_mut(locale);
+        for (key, value, metadata) in imported {
+            let path = escape_segment(&key).into_owned();
+            translations.set_entry(locale, key, value);
+            translations.add_metadata(locale, &path, metadata);
+        }
+        self.commit(translations);
+        Ok(report)
+    }
+
+    /// Formats a message, HTML-escaping string arguments if `options.escape`
+    /// is set.
+    fn format_message(&self, locale: &str, key: &str, args: JsValue, options: &TranslationOptions) -> Result<String, JsValue> {
+        Ok(join_parts(&self.format_message_parts(locale, key, args, options)?))
+    }
+
+    /// Formats a message into parts. Fluent messages, and strings that are
+    /// not valid ICU messages, come back as a single literal part.
+    fn format_message_parts(&self, locale: &str, key: &str, args: JsValue, options: &TranslationOptions) -> Result<Vec<Part>, JsValue> {
+        let translations = self.translations.read().unwrap();
+        let key = translations.resolve_key(locale, key, options.context.as_deref());
+        let key = key.as_ref();
+        let mut args_map: HashMap<String, FormatArg> = serde_wasm_bindgen::from_value(args)?;
+        let is_fluent = translations.is_fluent(locale, key);
+        let mode = options.format_mode(translations.format_mode);
+        if let Some(text) = translations.indexed(locale, key) {
+            if !is_fluent && mode == FormatMode::Lenient {
+                if options.escape {
+                    escape_args(&mut args_map);
+                }
+                return translations.format_text(text, locale, &args_map, options.icu);
+            }
+        }
+        let map = translations
+            .translations
+            .get(locale)
+            .ok_or("Locale not found")?;
+        let value = get_value_by_key(map, key)?;
+
+        let marker = options
+            .missing_marker
+            .as_deref()
+            .or(translations.missing_marker.as_deref())
+            .unwrap_or(DEFAULT_MISSING_MARKER);
+        check_arguments(value, is_fluent, options.icu, mode, marker, &mut args_map).map_err(|e| format!("{} for '{}'", e, key))?;
+        if options.escape {
+            escape_args(&mut args_map);
+        }
+        if is_fluent {
+            let scope = Scope { locale, map, args: &args_map };
+            return Ok(vec![Part::literal(scope.format_value(value)?)]);
+        }
+        match value {
+            TranslationValue::String(s) => translations.format_text(s, locale, &args_map, options.icu),
+            TranslationValue::Nested(_) => {
+                Err(JsValue::from("Translation is not a string".to_string()))
+            }
+        }
+    }
+
+    /// Takes the translations lock for a mutation; see [`Mutation`].
+    fn mutate(&self) -> Mutation<'_> {
+        Mutation(self.translations.write().unwrap())
+    }
+
+    /// Completes a mutation: records it in the history, releases the lock and
+    /// emits a `change` event per locale it changed.
+    fn commit(&self, mut translations: Mutation<'_>) {
+        let (changes, step) = translations.finish_mutation();
+        if let Some(history) = translations.history.as_mut().filter(|_| !step.is_empty()) {
+            history.push(step);
+        }
+        // Stop recording keys once the last `change` listener is gone.
+        translations.track_changes = self.listeners.borrow().has(Event::Change);
+        drop(translations);
+        self.emit_change_events(changes);
+    }
+
+    fn apply_batch(&self, ops: Vec<BatchOp>) -> Result<(), JsValue> {
+        let mut translations = self.mutate();
+
+        let mut working = HashMap::new();
+        for op in &ops {
+            if let Some(map) = translations.translations.get(op.locale()) {
+                working.entry(op.locale().to_string()).or_insert_with(|| map.clone());
+            }
+        }
+        apply_ops(&mut working, &ops)?;
+
+        for op in &ops {
+            match op {
+                BatchOp::Set { locale, key, .. } | BatchOp::Update { locale, key, .. } => {
+                    translations.touch(locale, key);
+                }
+                BatchOp::Delete { locale, key } => {
+                    translations.touch(locale, key);
+                    translations.remove_metadata(locale, key);
+                }
+            }
+        }
+        for (locale, map) in working {
+            if let Some(index) = translations.index.as_mut() {
+                index.rebuild_locale(&locale, &map);
+            }
+            translations.translations.insert(locale, map);
+        }
+        for op in &ops {
+            translations.forget_fluent(op.locale(), op.key());
+        }
+        self.commit(translations);
+        Ok(())
+    }
+
+    /// Undoes (`undo == true`) or redoes the latest step, moving it to the
+    /// other stack.
+    fn replay(&self, undo: bool) -> Result<bool, JsValue> {
+        let mut translations = self.mutate();
+        let step = match translations.history.as_mut() {
+            Some(history) if undo => history.pop_undo(),
+            Some(history) => history.pop_redo(),
+            None => None,
+        };
+        let Some(step) = step else {
+            return Ok(false);
+        };
+
+        // Replay into copies of the affected locales so a step that no longer
+        // applies leaves everything, including the history, as it was.
+        let entries: Vec<(&Entry, &Option<TranslationValue>)> = if undo {
+            step.entries.iter().rev().map(|entry| (entry, &entry.before)).collect()
+        } else {
+            step.entries.iter().map(|entry| (entry, &entry.after)).collect()
+        };
+        let mut working: HashMap<String, HashMap<String, TranslationValue>> = HashMap::new();
+        let result = entries.iter().try_for_each(|(entry, value)| {
+            let map = working
+                .entry(entry.locale.clone())
+                .or_insert_with(|| translations.translations.get(&entry.locale).cloned().unwrap_or_default());
+            let keys = split_key(&entry.key);
+            match value {
+                Some(value) => update_translation_recursive(map, &keys, value.clone()),
+                None => {
+                    remove_translation_recursive(map, &keys, false);
+                    Ok(())
+                }
+            }
+        });
+        if let Err(error) = result {
+            if let Some(history) = translations.history.as_mut() {
+                if undo {
+                    history.push_undo(step);
+                } else {
+                    history.push_redo(step);
+                }
+            }
+            return Err(JsValue::from(error));
+        }
+
+        for (entry, _) in &entries {
+            translations.touch(&entry.locale, &entry.key);
+        }
+        for (locale, map) in working {
+            if let Some(index) = translations.index.as_mut() {
+                index.rebuild_locale(&locale, &map);
+            }
+            translations.translations.insert(locale, map);
+        }
+        for (entry, _) in &entries {
+            let (metadata, fluent) = if undo {
+                (&entry.metadata_before, entry.fluent_before)
+            } else {
+                (&entry.metadata_after, entry.fluent_after)
+            };
+            translations.restore_entry(&entry.locale, &entry.key, metadata, fluent);
+        }
+        for entry in &step.locales {
+            translations.restore_locale(&entry.locale, if undo { entry.before } else { entry.after });
+        }
+
+        let (changes, _) = translations.finish_mutation();
+        if let Some(history) = translations.history.as_mut() {
+            if undo {
+                history.push_redo(step);
+            } else {
+                history.push_undo(step);
+            }
+        }
+        drop(translations);
+        self.emit_change_events(changes);
+        Ok(true)
+    }
+
+    fn emit_change_events(&self, changes: BTreeMap<String, BTreeSet<String>>) {
+        for (locale, keys) in changes {
+            self.emit(Event::Change, &ChangeEvent { locale, keys: keys.into_iter().collect() });
+        }
+    }
+
+    fn emit<T: Serialize>(&self, event: Event, payload: &T) {
+        if !self.listeners.borrow().has(event) {
+            return;
+        }
+        if let Ok(payload) = to_js_object(payload) {
+            emit(&self.listeners, event, &payload);
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let translations = self.translations.read().unwrap();
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            settings: Settings {
+                key_index: translations.index.is_some(),
+                format_mode: translations.format_mode,
+                missing_marker: translations.missing_marker.clone(),
+                locale: translations.locale.clone(),
+                fluent_keys: translations
+                    .fluent_keys
+                    .iter()
+                    .map(|(locale, keys)| (locale.clone(), keys.iter().cloned().collect()))
+                    .collect(),
+            },
+            translations: translations.translations.clone(),
+            metadata: translations.metadata.clone(),
+        }
+    }
+
+    fn apply_settings(&self, settings: &Settings) {
+        self.set_key_index_enabled(settings.key_index);
+        let mut translations = self.translations.write().unwrap();
+        translations.fluent_keys = settings
+            .fluent_keys
+            .iter()
+            .map(|(locale, keys)| (locale.clone(), keys.iter().cloned().collect()))
+            .collect();
+        translations.format_mode = settings.format_mode;
+        translations.missing_marker = settings.missing_marker.clone();
+        translations.locale = settings.locale.clone();
+    }
+}
+
+
+/// Encodes a translations JSON document (`{ "en": { "hello": "Hello" } }`) into
+/// the binary bundle format accepted by `I18n.loadBundle`.
+///
+/// # Example
+/// ```js
+/// import { encodeBundle } from "wasm-i18n";
+/// const bytes = encodeBundle(JSON.stringify({ en: { hello: "Hello" } }));
+/// ```
+#[wasm_bindgen(js_name = encodeBundle)]
+pub fn encode_bundle(json: &str) -> Result<Vec<u8>, JsValue> {
+    encode_bundle_json(json).map_err(JsValue::from)
+}
+
+/// Parses a YAML document into the structure `setTranslations` accepts.
+///
+/// # Example
+/// ```js
+/// import { parseYaml } from "wasm-i18n";
+/// const { en } = parseYaml("en:\n  hello: Hello\n");
+/// i18n.setTranslations('en', en);
+/// ```
+#[wasm_bindgen(js_name = parseYaml)]
+pub fn parse_yaml_translations(text: &str) -> Result<JsValue, JsValue> {
+    to_js_object(&parse_yaml(text)?)
+}
+
+/// Parses a TOML document into the structure `setTranslations` accepts.
+///
+/// # Example
+/// ```js
+/// import { parseToml } from "wasm-i18n";
+/// i18n.setTranslations('en', parseToml('[menu]\nopen = "Open"'));
+/// ```
+#[wasm_bindgen(js_name = parseToml)]
+pub fn parse_toml_translations(text: &str) -> Result<JsValue, JsValue> {
+    to_js_object(&parse_toml(text)?)
+}
+
+/// Parses a Java `.properties` file into the structure `setTranslations`
+/// accepts. Dotted keys become nested objects and `\uXXXX` escapes are decoded.
+///
+/// # Example
+/// ```js
+/// import { parseProperties } from "wasm-i18n";
+/// i18n.setTranslations('de', parseProperties('menu.open=\\u00D6ffnen'));
+/// ```
+#[wasm_bindgen(js_name = parseProperties)]
+pub fn parse_properties_translations(text: &str) -> Result<JsValue, JsValue> {
+    to_js_object(&parse_properties(text)?)
+}
+
+/// Turns markup nodes into an array of strings and tag callback results.
+fn render_rich(nodes: &[MarkupNode], callbacks: &HashMap<String, js_sys::Function>) -> Result<js_sys::Array, JsValue> {
+    let parts = js_sys::Array::new();
+    for node in nodes {
+        match node {
+            MarkupNode::Text { value } => {
+                parts.push(&JsValue::from(value.as_str()));
+            }
+            MarkupNode::Tag { name, children } => {
+                let children = render_rich(children, callbacks)?;
+                let callback = callbacks.get(name).ok_or_else(|| format!("No callback for tag '{}'", name))?;
+                parts.push(&callback.call1(&JsValue::NULL, &children)?);
+            }
+        }
+    }
+    Ok(parts)
+}
+
+/// Copies the bytes of an `ArrayBuffer` or `Uint8Array` into wasm memory.
+fn binary_bytes(data: &JsValue) -> Result<Vec<u8>, JsValue> {
+    if let Some(array) = data.dyn_ref::<js_sys::Uint8Array>() {
+        Ok(array.to_vec())
+    } else if data.is_instance_of::<js_sys::ArrayBuffer>() {
+        Ok(js_sys::Uint8Array::new(data).to_vec())
+    } else {
+        Err(JsValue::from("Expected an ArrayBuffer or Uint8Array"))
+    }
+}
+
+/// Serializes maps as plain JS objects rather than `Map`s.
+fn to_js_object<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, JsValue> {
+    value.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(JsValue::from)
+}
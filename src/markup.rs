@@ -0,0 +1,211 @@
+//! Markup in formatted messages: HTML escaping of arguments and a tree of
+//! text and tag nodes for rendering without `innerHTML`.
+//!
+//! A formatted message is read as HTML in which arguments have already been
+//! escaped. Tags are recognised only when allowed and written without
+//! attributes (`<b>`, `</b>`, `<br/>`); anything else, including `<script>` or
+//! `<a href="...">`, stays literal text. Entities in text are decoded, so a
+//! text node holds exactly what the user should see.
+
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::helpers::FormatArg;
+
+/// Tags kept as markup by default when escaping.
+pub const DEFAULT_ALLOWED_TAGS: [&str; 13] = [
+    "b", "strong", "i", "em", "u", "s", "small", "mark", "sub", "sup", "code", "span", "br",
+];
+
+const VOID_TAGS: [&str; 2] = ["br", "wbr"];
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum MarkupNode {
+    Text { value: String },
+    Tag { name: String, children: Vec<MarkupNode> },
+}
+
+/// Escapes `& < > " '` for use in HTML text and attribute values.
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escapes the string arguments of a format call.
+pub fn escape_args(args: &mut HashMap<String, FormatArg>) {
+    for value in args.values_mut() {
+        if let FormatArg::String(text) = value {
+            *text = escape_html(text);
+        }
+    }
+}
+
+/// Splits `html` into text and tag nodes. Tag names keep their case but close
+/// case-insensitively; unmatched closing tags are kept as text and tags left
+/// open at the end are closed implicitly.
+pub fn parse_markup(html: &str, allowed: impl Fn(&str) -> bool) -> Vec<MarkupNode> {
+    // Open tags with the children collected so far; the root has no name.
+    let mut stack: Vec<(Option<String>, Vec<MarkupNode>)> = vec![(None, Vec::new())];
+    let mut text = String::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let tag = match parse_tag(rest) {
+            Some(tag) if allowed(tag.name) => tag,
+            _ => {
+                text.push('<');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+        let name = tag.name.to_string();
+
+        if tag.closing {
+            let Some(depth) = stack
+                .iter()
+                .rposition(|(open, _)| open.as_deref().is_some_and(|open| open.eq_ignore_ascii_case(&name)))
+            else {
+                text.push_str(&rest[..tag.len]);
+                rest = &rest[tag.len..];
+                continue;
+            };
+            flush_text(&mut stack, &mut text);
+            while stack.len() > depth {
+                close(&mut stack);
+            }
+        } else {
+            flush_text(&mut stack, &mut text);
+            if tag.self_closing || is_void(&name) {
+                push(&mut stack, MarkupNode::Tag { name, children: Vec::new() });
+            } else {
+                stack.push((Some(name), Vec::new()));
+            }
+        }
+        rest = &rest[tag.len..];
+    }
+    text.push_str(rest);
+    flush_text(&mut stack, &mut text);
+    while stack.len() > 1 {
+        close(&mut stack);
+    }
+    stack.pop().map(|(_, nodes)| nodes).unwrap_or_default()
+}
+
+/// Writes nodes back as HTML with all text escaped.
+pub fn render_html(nodes: &[MarkupNode]) -> String {
+    let mut out = String::new();
+    for node in nodes {
+        match node {
+            MarkupNode::Text { value } => out.push_str(&escape_html(value)),
+            MarkupNode::Tag { name, children } => {
+                out.push_str(&format!("<{}>", name));
+                if !is_void(name) {
+                    out.push_str(&render_html(children));
+                    out.push_str(&format!("</{}>", name));
+                }
+            }
+        }
+    }
+    out
+}
+
+fn is_void(name: &str) -> bool {
+    VOID_TAGS.iter().any(|tag| tag.eq_ignore_ascii_case(name))
+}
+
+struct Tag<'a> {
+    name: &'a str,
+    closing: bool,
+    self_closing: bool,
+    /// Length of the tag in bytes, including `<` and `>`.
+    len: usize,
+}
+
+/// Reads an attribute-free tag at the start of `text`.
+fn parse_tag(text: &str) -> Option<Tag<'_>> {
+    let inner = &text[1..text.find('>')?];
+    let (closing, inner) = match inner.strip_prefix('/') {
+        Some(inner) => (true, inner),
+        None => (false, inner),
+    };
+    let (self_closing, name) = match inner.strip_suffix('/') {
+        Some(name) if !closing => (true, name.trim_end()),
+        _ => (false, inner),
+    };
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| Tag { name, closing, self_closing, len: inner.len() + 2 + usize::from(closing) })
+}
+
+fn push(stack: &mut [(Option<String>, Vec<MarkupNode>)], node: MarkupNode) {
+    if let Some((_, children)) = stack.last_mut() {
+        children.push(node);
+    }
+}
+
+fn flush_text(stack: &mut [(Option<String>, Vec<MarkupNode>)], text: &mut String) {
+    if !text.is_empty() {
+        let value = decode_entities(&std::mem::take(text));
+        push(stack, MarkupNode::Text { value });
+    }
+}
+
+fn close(stack: &mut Vec<(Option<String>, Vec<MarkupNode>)>) {
+    if let Some((Some(name), children)) = stack.pop() {
+        push(stack, MarkupNode::Tag { name, children });
+    }
+}
+
+/// Decodes the named entities `escape_html` produces plus `&nbsp;` and
+/// numeric references; anything else is left as written.
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest[1..].find(';').filter(|end| *end <= 10).and_then(|end| {
+            let entity = &rest[1..end + 1];
+            let c = match entity {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                "nbsp" => '\u{a0}',
+                _ => {
+                    let code = match entity.strip_prefix('#')? {
+                        hex if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok()?,
+                        decimal => decimal.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, end + 2))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
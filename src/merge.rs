@@ -0,0 +1,125 @@
+//! Strategies `setTranslations` uses to combine incoming translations with a
+//! locale's existing ones.
+//!
+//! A type conflict is a key that holds a string on one side and a nested map
+//! on the other. The merging strategies resolve it like any other clash, by
+//! the incoming value winning (or, for `keep-existing`, the existing one);
+//! with `onConflict: "error"` the conflicts are reported and nothing is
+//! written.
+
+use std::collections::HashMap;
+use serde::Deserialize;
+use wasm_bindgen::JsValue;
+use crate::helpers::{join_key, merge_translation_values};
+use crate::TranslationValue;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeStrategy {
+    /// Drop the locale's existing translations.
+    Replace,
+    /// Replace top-level keys as a whole.
+    Shallow,
+    /// Merge nested maps recursively; the incoming value wins elsewhere.
+    #[default]
+    Deep,
+    /// Merge nested maps recursively but never overwrite an existing key.
+    KeepExisting,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictMode {
+    /// Resolve type conflicts by the strategy.
+    #[default]
+    Overwrite,
+    /// Fail with the conflicting keys, leaving the locale unchanged.
+    Error,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MergeOptions {
+    pub strategy: MergeStrategy,
+    pub on_conflict: ConflictMode,
+}
+
+impl MergeOptions {
+    /// Reads options from JS, treating `undefined` and `null` as no options.
+    pub fn from_js(value: JsValue) -> Result<MergeOptions, JsValue> {
+        if value.is_undefined() || value.is_null() {
+            return Ok(MergeOptions::default());
+        }
+        serde_wasm_bindgen::from_value(value).map_err(JsValue::from)
+    }
+}
+
+/// Merges `value` into `map` at the top-level `key`. `Replace` is handled by
+/// the caller, which swaps the whole locale, and merges like `Shallow` here.
+pub fn merge_entry(map: &mut HashMap<String, TranslationValue>, key: String, value: TranslationValue, strategy: MergeStrategy) {
+    match strategy {
+        MergeStrategy::Replace | MergeStrategy::Shallow => {
+            map.insert(key, value);
+        }
+        MergeStrategy::Deep => {
+            map.entry(key)
+                .and_modify(|existing| merge_translation_values(existing, value.clone()))
+                .or_insert(value);
+        }
+        MergeStrategy::KeepExisting => {
+            map.entry(key)
+                .and_modify(|existing| keep_existing(existing, value.clone()))
+                .or_insert(value);
+        }
+    }
+}
+
+fn keep_existing(existing: &mut TranslationValue, new: TranslationValue) {
+    if let (TranslationValue::Nested(existing_map), TranslationValue::Nested(new_map)) = (existing, new) {
+        for (key, value) in new_map {
+            existing_map
+                .entry(key)
+                .and_modify(|existing_value| keep_existing(existing_value, value.clone()))
+                .or_insert(value);
+        }
+    }
+}
+
+/// The dotted keys where `incoming` and `existing` disagree on whether the
+/// key holds a string or a map, as far as `strategy` compares them, sorted.
+pub fn type_conflicts(
+    existing: &HashMap<String, TranslationValue>,
+    incoming: &HashMap<String, TranslationValue>,
+    strategy: MergeStrategy,
+) -> Vec<String> {
+    let mut conflicts = Vec::new();
+    match strategy {
+        MergeStrategy::Replace => {}
+        MergeStrategy::Shallow => collect_conflicts("", existing, incoming, false, &mut conflicts),
+        MergeStrategy::Deep | MergeStrategy::KeepExisting => {
+            collect_conflicts("", existing, incoming, true, &mut conflicts)
+        }
+    }
+    conflicts.sort();
+    conflicts
+}
+
+fn collect_conflicts(
+    prefix: &str,
+    existing: &HashMap<String, TranslationValue>,
+    incoming: &HashMap<String, TranslationValue>,
+    recursive: bool,
+    out: &mut Vec<String>,
+) {
+    for (key, value) in incoming {
+        let path = join_key(prefix, key);
+        match (existing.get(key), value) {
+            (Some(TranslationValue::Nested(existing)), TranslationValue::Nested(incoming)) if recursive => {
+                collect_conflicts(&path, existing, incoming, recursive, out);
+            }
+            (Some(TranslationValue::String(_)), TranslationValue::Nested(_))
+            | (Some(TranslationValue::Nested(_)), TranslationValue::String(_)) => out.push(path),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Formatting of ICU messages into typed parts.
+//!
+//! `formatToParts` returns the parts and `formatTranslation` with `icu: true`
+//! joins them, so both read the same parsed message. Number and date arguments are written
+//! as passed; their `style` is reported so the host can apply `Intl`
+//! formatting to just that part. Arguments that are missing are written back
+//! as `{name}`, and a plural or select without its argument takes `other`.
+//! The `#` of a plural whose argument is not a number shows the argument as
+//! passed.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use crate::helpers::{ordinal_category, plural_category, FormatArg};
+use crate::icu::{self, ArgumentKind, Node};
+
+/// Messages are parsed again once the cache holds this many texts.
+const MAX_CACHED_MESSAGES: usize = 1024;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum PartKind {
+    Literal,
+    Argument,
+    Number,
+    Date,
+    /// The `#` of a plural option: the plural value less any offset.
+    Plural,
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct Part {
+    #[serde(rename = "type")]
+    pub kind: PartKind,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<String>,
+}
+
+impl Part {
+    pub fn literal(value: String) -> Part {
+        Part { kind: PartKind::Literal, value, name: None, style: None }
+    }
+}
+
+/// Parsed messages keyed by their text, so formatting the same string again
+/// skips the parser. Texts that are not valid ICU messages are cached as
+/// `None`.
+#[derive(Default)]
+pub struct MessageCache {
+    messages: Mutex<HashMap<String, Option<Arc<Vec<Node>>>>>,
+}
+
+impl MessageCache {
+    pub fn parse(&self, text: &str) -> Option<Arc<Vec<Node>>> {
+        let mut messages = self.messages.lock().unwrap();
+        if let Some(nodes) = messages.get(text) {
+            return nodes.clone();
+        }
+        if messages.len() >= MAX_CACHED_MESSAGES {
+            messages.clear();
+        }
+        let nodes = icu::parse(text).ok().map(Arc::new);
+        messages.insert(text.to_string(), nodes.clone());
+        nodes
+    }
+}
+
+/// Formats a parsed message with `args`.
+pub fn format_to_parts(nodes: &[Node], locale: &str, args: &HashMap<String, FormatArg>) -> Vec<Part> {
+    let mut parts = Vec::new();
+    Formatter { locale, args }.nodes(nodes, None, &mut parts);
+    parts
+}
+
+pub fn join_parts(parts: &[Part]) -> String {
+    parts.iter().map(|part| part.value.as_str()).collect()
+}
+
+struct Formatter<'a> {
+    locale: &'a str,
+    args: &'a HashMap<String, FormatArg>,
+}
+
+/// The plural argument a `#` refers to, with its value less the offset.
+struct Pound<'a> {
+    name: &'a str,
+    value: Option<String>,
+}
+
+impl Formatter<'_> {
+    fn nodes(&self, nodes: &[Node], pound: Option<&Pound>, parts: &mut Vec<Part>) {
+        for node in nodes {
+            match node {
+                Node::Text(text) => push_literal(parts, text),
+                Node::Pound => match pound {
+                    Some(Pound { name, value: Some(value) }) => parts.push(Part {
+                        kind: PartKind::Plural,
+                        value: value.clone(),
+                        name: Some(name.to_string()),
+                        style: None,
+                    }),
+                    Some(Pound { name, value: None }) => push_literal(parts, &format!("{{{}}}", name)),
+                    None => push_literal(parts, "#"),
+                },
+                Node::Argument(argument) => self.argument(argument, parts),
+            }
+        }
+    }
+
+    fn argument(&self, argument: &icu::Argument, parts: &mut Vec<Part>) {
+        let name = argument.name.as_str();
+        let arg = self.args.get(name);
+        let (kind, style) = match &argument.kind {
+            ArgumentKind::Plain => (PartKind::Argument, None),
+            ArgumentKind::Number(style) => (PartKind::Number, style.clone()),
+            ArgumentKind::Date(style) | ArgumentKind::Time(style) => (PartKind::Date, style.clone()),
+            ArgumentKind::Plural { ordinal, offset, options } => {
+                let n = arg.and_then(number);
+                let (selected, pound) = match n {
+                    Some(n) => {
+                        let exact = options.iter().find(|(selector, _)| {
+                            selector.strip_prefix('=').and_then(|exact| exact.parse::<f64>().ok()) == Some(n)
+                        });
+                        let selected = exact.map(|(_, message)| message).or_else(|| {
+                            let category = if *ordinal {
+                                ordinal_category(self.locale, n - offset)
+                            } else {
+                                plural_category(self.locale, n - offset)
+                            };
+                            find_option(options, &category)
+                        });
+                        (selected, Some(FormatArg::Number(n - offset).to_string()))
+                    }
+                    // A value that is not a number, such as a missing-argument
+                    // marker, is shown as passed.
+                    None => (None, arg.map(FormatArg::to_string)),
+                };
+                let message = selected.or_else(|| find_option(options, "other"));
+                let pound = Pound { name, value: pound };
+                self.nodes(message.map_or(&[][..], Vec::as_slice), Some(&pound), parts);
+                return;
+            }
+            ArgumentKind::Select(options) => {
+                let selected = arg.and_then(|arg| find_option(options, &arg.to_string()));
+                let message = selected.or_else(|| find_option(options, "other"));
+                self.nodes(message.map_or(&[][..], Vec::as_slice), None, parts);
+                return;
+            }
+        };
+        match arg {
+            Some(arg) => parts.push(Part { kind, value: arg.to_string(), name: Some(name.to_string()), style }),
+            None => push_literal(parts, &format!("{{{}}}", name)),
+        }
+    }
+}
+
+fn find_option<'a>(options: &'a [(String, Vec<Node>)], selector: &str) -> Option<&'a Vec<Node>> {
+    options.iter().find(|(option, _)| option == selector).map(|(_, message)| message)
+}
+
+fn number(arg: &FormatArg) -> Option<f64> {
+    match arg {
+        FormatArg::Number(n) => Some(*n),
+        FormatArg::String(s) => s.trim().parse().ok(),
+        FormatArg::Bool(_) => None,
+    }
+}
+
+/// Appends literal text, merging it into a preceding literal part.
+fn push_literal(parts: &mut Vec<Part>, text: &str) {
+    match parts.last_mut() {
+        Some(Part { kind: PartKind::Literal, value, .. }) => value.push_str(text),
+        _ => parts.push(Part::literal(text.to_string())),
+    }
+}
@@ -0,0 +1,76 @@
+//! Per-key translation metadata: translator descriptions, context, length
+//! limits, placeholder docs and modification times.
+//!
+//! Metadata is stored next to a locale's translation tree, keyed by dotted
+//! path, and never affects lookups or formatting. Fields this crate does not
+//! know about (from ARB files, for example) are kept in `extra`.
+
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_length: Option<u32>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub placeholders: BTreeMap<String, PlaceholderMetadata>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaceholderMetadata {
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub example: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl KeyMetadata {
+    /// Reads metadata from a JSON object such as an ARB `@key` entry.
+    pub fn from_object(object: Map<String, Value>) -> Result<KeyMetadata, String> {
+        serde_json::from_value(Value::Object(object)).map_err(|e| format!("Invalid metadata: {}", e))
+    }
+
+    pub fn to_object(&self) -> Map<String, Value> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(object)) => object,
+            _ => Map::new(),
+        }
+    }
+
+    /// Overlays the fields set in `other` onto `self`.
+    pub fn merge(&mut self, other: KeyMetadata) {
+        if other.description.is_some() {
+            self.description = other.description;
+        }
+        if other.context.is_some() {
+            self.context = other.context;
+        }
+        if other.max_length.is_some() {
+            self.max_length = other.max_length;
+        }
+        if other.last_modified.is_some() {
+            self.last_modified = other.last_modified;
+        }
+        self.placeholders.extend(other.placeholders);
+        self.extra.extend(other.extra);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == KeyMetadata::default()
+    }
+}
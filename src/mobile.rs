@@ -0,0 +1,746 @@
+//! Android `strings.xml` and Apple `.strings` / `.stringsdict` import and export.
+//!
+//! printf-style placeholders (`%1$s`, `%@`, `%d`) are translated into the
+//! crate's `{name}` style using their 1-based position (`{1}`, `{2}`, ...), and
+//! back again on export, where named placeholders are numbered in order of
+//! first appearance. Plurals become maps keyed by CLDR category and Android
+//! string arrays become maps keyed by index.
+//!
+//! Resource names are dotted paths (`menu.open`, with `\.` for a literal
+//! dot), so nested maps survive an export and import. A name with an empty
+//! segment or a segment padded with whitespace, such as the `.strings` key
+//! `"Loading..."` or `"Hello. World"`, is a single literal key instead, and is
+//! exported as it is.
+//!
+//! Android's styling tags (`<b>`, `<i>`, `<u>`, ...) are kept as
+//! attribute-free tags that `formatRich` understands; other inline elements,
+//! such as `<xliff:g>`, are replaced by their text.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use crate::helpers::{escape_segment, is_plural_map, join_key, key_segments, split_key, PLURAL_CATEGORIES};
+use crate::xml::{escape, parse, Element, XmlNode};
+use crate::TranslationValue;
+
+/// The dotted key the resource `name` is imported as: `name` itself, or
+/// `name` as one escaped segment when it is a literal name.
+pub fn resource_key(name: &str) -> Cow<'_, str> {
+    if key_segments(name).any(|segment| segment.is_empty() || segment.trim() != segment) {
+        escape_segment(name)
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+/// The resource name of the dotted `path`: its segments joined by plain dots
+/// when that reads back as the same key, the escaped path otherwise.
+fn resource_name(path: &str) -> String {
+    let plain = key_segments(path).collect::<Vec<_>>().join(".");
+    if split_key(&resource_key(&plain)) == split_key(path) {
+        plain
+    } else {
+        path.to_string()
+    }
+}
+
+/// Converts printf-style placeholders into `{N}` placeholders.
+pub fn printf_to_named(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.char_indices().peekable();
+    let mut next_position = 1;
+
+    while let Some((start, c)) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match parse_specifier(&s[start + 1..]) {
+            Some((Specifier::Percent, len)) => {
+                out.push('%');
+                skip(&mut chars, len);
+            }
+            Some((Specifier::Argument(position), len)) => {
+                let position = position.unwrap_or_else(|| {
+                    let position = next_position;
+                    next_position += 1;
+                    position
+                });
+                out.push_str(&format!("{{{}}}", position));
+                skip(&mut chars, len);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+fn skip<I: Iterator>(iter: &mut I, n: usize) {
+    for _ in 0..n {
+        iter.next();
+    }
+}
+
+enum Specifier {
+    Percent,
+    Argument(Option<usize>),
+}
+
+/// Parses the part of a conversion specifier after `%`, returning the
+/// specifier and its length in chars.
+fn parse_specifier(s: &str) -> Option<(Specifier, usize)> {
+    if s.starts_with('%') {
+        return Some((Specifier::Percent, 1));
+    }
+
+    let chars: Vec<char> = s.chars().take(16).collect();
+    let mut i = 0;
+    let digits = chars.iter().take_while(|c| c.is_ascii_digit()).count();
+    let mut position = None;
+    if digits > 0 && chars.get(digits) == Some(&'$') {
+        position = chars[..digits].iter().collect::<String>().parse().ok();
+        i = digits + 1;
+    }
+    while matches!(chars.get(i), Some('-' | '+' | ' ' | '#' | '0')) {
+        i += 1;
+    }
+    while matches!(chars.get(i), Some(c) if c.is_ascii_digit()) {
+        i += 1;
+    }
+    if chars.get(i) == Some(&'.') {
+        i += 1;
+        while matches!(chars.get(i), Some(c) if c.is_ascii_digit()) {
+            i += 1;
+        }
+    }
+    while matches!(chars.get(i), Some('h' | 'l' | 'q' | 'z' | 't' | 'j' | 'L')) {
+        i += 1;
+    }
+    match chars.get(i) {
+        Some(c) if "diouxXeEfFgGaAcsSp@".contains(*c) => Some((Specifier::Argument(position), i + 1)),
+        _ => None,
+    }
+}
+
+/// Assigns printf positions to the `{name}` placeholders of related texts,
+/// such as all forms of a plural. Numeric names keep their number, other names
+/// are numbered in order of first appearance.
+pub fn printf_positions<'a>(texts: &[&'a str]) -> HashMap<&'a str, usize> {
+    let mut names: Vec<&str> = Vec::new();
+    for name in texts.iter().flat_map(|text| placeholder_names(text)) {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    let mut positions = HashMap::new();
+    let mut used = Vec::new();
+    for name in &names {
+        if let Ok(n) = name.parse::<usize>() {
+            positions.insert(*name, n);
+            used.push(n);
+        }
+    }
+    let mut next = 1;
+    for name in names {
+        if positions.contains_key(name) {
+            continue;
+        }
+        while used.contains(&next) {
+            next += 1;
+        }
+        positions.insert(name, next);
+        used.push(next);
+    }
+    positions
+}
+
+/// Converts `{name}` placeholders into positional printf placeholders and
+/// escapes literal `%`. `conversion` picks the conversion character per name.
+pub fn named_to_printf(s: &str, positions: &HashMap<&str, usize>, conversion: impl Fn(&str) -> char) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(c) = rest.chars().next() {
+        if c == '{' {
+            if let Some(end) = rest.find('}') {
+                let name = &rest[1..end];
+                if let Some(position) = positions.get(name) {
+                    out.push_str(&format!("%{}${}", position, conversion(name)));
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        if c == '%' {
+            out.push('%');
+        }
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    out
+}
+
+/// Converts a standalone text, using the same conversion for every placeholder.
+fn to_printf(s: &str, conversion: char) -> String {
+    named_to_printf(s, &printf_positions(&[s]), |_| conversion)
+}
+
+/// Names of the `{name}` placeholders in `s`, in order of first appearance.
+fn placeholder_names(s: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = s;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) => {
+                let name = &after[..end];
+                let valid = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+                if valid && !names.contains(&name) {
+                    names.push(name);
+                }
+                rest = &after[end + 1..];
+            }
+            None => break,
+        }
+    }
+    names
+}
+
+pub fn parse_android(text: &str) -> Result<Vec<(String, TranslationValue)>, String> {
+    let root = parse(text)?;
+    if root.name != "resources" {
+        return Err(format!("Invalid strings.xml: unexpected root element '{}'", root.name));
+    }
+
+    let mut entries = Vec::new();
+    for element in root.elements() {
+        let name = match element.attr("name") {
+            Some(name) => name.to_string(),
+            None => continue,
+        };
+        let value = match element.name.as_str() {
+            "string" => TranslationValue::String(android_text(element)),
+            "plurals" => TranslationValue::Nested(
+                element
+                    .children_named("item")
+                    .filter_map(|item| Some((item.attr("quantity")?.to_string(), TranslationValue::String(android_text(item)))))
+                    .collect(),
+            ),
+            "string-array" => TranslationValue::Nested(
+                element
+                    .children_named("item")
+                    .enumerate()
+                    .map(|(i, item)| (i.to_string(), TranslationValue::String(android_text(item))))
+                    .collect(),
+            ),
+            _ => continue,
+        };
+        entries.push((name, value));
+    }
+    Ok(entries)
+}
+
+/// Android styling tags kept as markup on import and written back on export.
+const STYLE_TAGS: [&str; 6] = ["b", "i", "u", "small", "sub", "sup"];
+
+/// Decodes Android's string escaping, quoting and styling, then converts
+/// placeholders.
+fn android_text(element: &Element) -> String {
+    let mut text = AndroidText::default();
+    text.push_children(element);
+    printf_to_named(&text.out)
+}
+
+#[derive(Default)]
+struct AndroidText {
+    out: String,
+    quoted: bool,
+    /// Unquoted whitespace seen since the last character, written only once
+    /// something follows it so that the text ends up trimmed.
+    space: bool,
+}
+
+impl AndroidText {
+    fn push_children(&mut self, element: &Element) {
+        for child in &element.children {
+            match child {
+                XmlNode::Text(raw) => self.push_raw(raw),
+                XmlNode::Element(inner) if STYLE_TAGS.contains(&inner.name.as_str()) => {
+                    self.push_str(&format!("<{}>", inner.name));
+                    self.push_children(inner);
+                    self.push_str(&format!("</{}>", inner.name));
+                }
+                XmlNode::Element(inner) => self.push_children(inner),
+            }
+        }
+    }
+
+    fn push_raw(&mut self, raw: &str) {
+        let mut chars = raw.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => self.quoted = !self.quoted,
+                '\\' => match chars.next() {
+                    Some('n') => self.push('\n'),
+                    Some('t') => self.push('\t'),
+                    Some('u') => {
+                        let hex: String = chars.by_ref().take(4).collect();
+                        if let Some(c) = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                            self.push(c);
+                        }
+                    }
+                    Some(other) => self.push(other),
+                    None => {}
+                },
+                // Outside of quotes Android collapses runs of whitespace.
+                c if c.is_whitespace() && !self.quoted => self.space = !self.out.is_empty(),
+                c => self.push(c),
+            }
+        }
+    }
+
+    fn push(&mut self, c: char) {
+        if std::mem::take(&mut self.space) && !self.out.ends_with(' ') {
+            self.out.push(' ');
+        }
+        self.out.push(c);
+    }
+
+    fn push_str(&mut self, s: &str) {
+        for c in s.chars() {
+            self.push(c);
+        }
+    }
+}
+
+/// Escapes a text for a `strings.xml` value. Texts whose whitespace Android
+/// would collapse are wrapped in double quotes.
+fn android_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == '<' {
+            if let Some(tag) = style_tag(&s[i..]) {
+                out.push_str(tag);
+                skip(&mut chars, tag.len() - 1);
+                continue;
+            }
+        }
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\'' => out.push_str("\\'"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '@' | '?' if i == 0 => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    if s.starts_with(char::is_whitespace) || s.ends_with(char::is_whitespace) || s.contains("  ") {
+        out = format!("\"{}\"", out);
+    }
+    out
+}
+
+/// The styling tag (`<b>` or `</b>`) at the start of `s`, if any.
+fn style_tag(s: &str) -> Option<&str> {
+    let end = s.find('>')?;
+    let name = s[1..end].strip_prefix('/').unwrap_or(&s[1..end]);
+    STYLE_TAGS.contains(&name).then(|| &s[..=end])
+}
+
+pub fn write_android(map: &HashMap<String, TranslationValue>) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n");
+    for (path, value) in flatten_resources(map) {
+        let name = escape(&resource_name(&path));
+        match value {
+            Resource::String(text) => out.push_str(&format!(
+                "    <string name=\"{}\">{}</string>\n",
+                name,
+                android_escape(&to_printf(text, 's'))
+            )),
+            Resource::Plural(forms) => {
+                let texts: Vec<&str> = forms.iter().map(|(_, text)| *text).collect();
+                let positions = printf_positions(&texts);
+                out.push_str(&format!("    <plurals name=\"{}\">\n", name));
+                for (quantity, text) in forms {
+                    out.push_str(&format!(
+                        "        <item quantity=\"{}\">{}</item>\n",
+                        quantity,
+                        android_escape(&named_to_printf(text, &positions, |_| 's'))
+                    ));
+                }
+                out.push_str("    </plurals>\n");
+            }
+            Resource::Array(items) => {
+                out.push_str(&format!("    <string-array name=\"{}\">\n", name));
+                for text in items {
+                    out.push_str(&format!("        <item>{}</item>\n", android_escape(&to_printf(text, 's'))));
+                }
+                out.push_str("    </string-array>\n");
+            }
+        }
+    }
+    out.push_str("</resources>\n");
+    out
+}
+
+enum Resource<'a> {
+    String(&'a str),
+    Plural(Vec<(&'a str, &'a str)>),
+    Array(Vec<&'a str>),
+}
+
+/// Flattens a translation tree into named resources, recognising plural maps
+/// and `{ "0": ..., "1": ... }` arrays, keyed by dotted path. Other nested
+/// maps are walked into.
+fn flatten_resources(map: &HashMap<String, TranslationValue>) -> BTreeMap<String, Resource<'_>> {
+    fn walk<'a>(prefix: &str, map: &'a HashMap<String, TranslationValue>, out: &mut BTreeMap<String, Resource<'a>>) {
+        for (key, value) in map {
+            let path = join_key(prefix, key);
+            match value {
+                TranslationValue::String(text) => {
+                    out.insert(path, Resource::String(text));
+                }
+                TranslationValue::Nested(inner) => {
+                    if let Some(forms) = plural_forms(inner) {
+                        out.insert(path, Resource::Plural(forms));
+                    } else if let Some(items) = array_items(inner) {
+                        out.insert(path, Resource::Array(items));
+                    } else {
+                        walk(&path, inner, out);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut out = BTreeMap::new();
+    walk("", map, &mut out);
+    out
+}
+
+fn plural_forms(map: &HashMap<String, TranslationValue>) -> Option<Vec<(&str, &str)>> {
+    if !is_plural_map(map) {
+        return None;
+    }
+    let mut forms: Vec<(&str, &str)> = map
+        .iter()
+        .filter_map(|(category, value)| match value {
+            TranslationValue::String(text) => Some((category.as_str(), text.as_str())),
+            TranslationValue::Nested(_) => None,
+        })
+        .collect();
+    forms.sort_by_key(|(category, _)| PLURAL_CATEGORIES.iter().position(|c| c == category));
+    Some(forms)
+}
+
+fn array_items(map: &HashMap<String, TranslationValue>) -> Option<Vec<&str>> {
+    if map.is_empty() {
+        return None;
+    }
+    (0..map.len())
+        .map(|i| match map.get(&i.to_string()) {
+            Some(TranslationValue::String(text)) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Parses an Apple `.strings` file (`"key" = "value";` with comments).
+pub fn parse_apple_strings(text: &str) -> Result<Vec<(String, String)>, String> {
+    let mut parser = StringsParser { chars: text.chars().collect(), pos: 0 };
+    let mut entries = Vec::new();
+    loop {
+        parser.skip_trivia()?;
+        if parser.pos >= parser.chars.len() {
+            return Ok(entries);
+        }
+        let key = parser.token()?;
+        parser.skip_trivia()?;
+        parser.expect('=')?;
+        parser.skip_trivia()?;
+        let value = parser.token()?;
+        parser.skip_trivia()?;
+        parser.expect(';')?;
+        entries.push((key, printf_to_named(&value)));
+    }
+}
+
+struct StringsParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl StringsParser {
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), String> {
+        loop {
+            match (self.peek(0), self.peek(1)) {
+                (Some(c), _) if c.is_whitespace() || c == '\u{feff}' => self.pos += 1,
+                (Some('/'), Some('/')) => {
+                    while !matches!(self.peek(0), None | Some('\n')) {
+                        self.pos += 1;
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    self.pos += 2;
+                    while !(self.peek(0) == Some('*') && self.peek(1) == Some('/')) {
+                        if self.peek(0).is_none() {
+                            return Err("Invalid .strings file: unterminated comment".to_string());
+                        }
+                        self.pos += 1;
+                    }
+                    self.pos += 2;
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.peek(0) == Some(c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("Invalid .strings file: expected '{}' at offset {}", c, self.pos))
+        }
+    }
+
+    /// Reads a quoted string or a bare token.
+    fn token(&mut self) -> Result<String, String> {
+        if self.peek(0) != Some('"') {
+            let start = self.pos;
+            while matches!(self.peek(0), Some(c) if c.is_alphanumeric() || "_.-$:/".contains(c)) {
+                self.pos += 1;
+            }
+            if start == self.pos {
+                return Err(format!("Invalid .strings file: expected a string at offset {}", self.pos));
+            }
+            return Ok(self.chars[start..self.pos].iter().collect());
+        }
+
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let c = self.peek(0).ok_or("Invalid .strings file: unterminated string")?;
+            self.pos += 1;
+            match c {
+                '"' => return Ok(out),
+                '\\' => {
+                    let escaped = self.peek(0).ok_or("Invalid .strings file: unterminated string")?;
+                    self.pos += 1;
+                    match escaped {
+                        'n' => out.push('\n'),
+                        't' => out.push('\t'),
+                        'r' => out.push('\r'),
+                        'u' | 'U' => {
+                            let hex: String = self.chars.iter().skip(self.pos).take(4).collect();
+                            let c = u32::from_str_radix(&hex, 16)
+                                .ok()
+                                .and_then(char::from_u32)
+                                .ok_or("Invalid .strings file: bad unicode escape")?;
+                            self.pos += 4;
+                            out.push(c);
+                        }
+                        other => out.push(other),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+    }
+}
+
+fn apple_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Writes every non-plural string of a locale as a `.strings` file.
+pub fn write_apple_strings(map: &HashMap<String, TranslationValue>) -> String {
+    let mut out = String::new();
+    for (path, value) in flatten_resources(map) {
+        match value {
+            Resource::String(text) => out.push_str(&format!(
+                "\"{}\" = \"{}\";\n",
+                apple_escape(&resource_name(&path)),
+                apple_escape(&to_printf(text, '@'))
+            )),
+            Resource::Array(items) => {
+                for (i, text) in items.into_iter().enumerate() {
+                    out.push_str(&format!(
+                        "\"{}\" = \"{}\";\n",
+                        apple_escape(&resource_name(&join_key(&path, &i.to_string()))),
+                        apple_escape(&to_printf(text, '@'))
+                    ));
+                }
+            }
+            // Plurals belong in the `.stringsdict` file.
+            Resource::Plural(_) => {}
+        }
+    }
+    out
+}
+
+/// Parses an Apple `.stringsdict` plist into plural maps keyed by category.
+pub fn parse_stringsdict(text: &str) -> Result<Vec<(String, TranslationValue)>, String> {
+    let root = parse(text)?;
+    let dict = match root.name.as_str() {
+        "plist" => root.child("dict"),
+        "dict" => Some(&root),
+        _ => None,
+    }
+    .ok_or("Invalid .stringsdict: expected a plist dictionary")?;
+
+    let mut entries = Vec::new();
+    for (key, value) in plist_dict(dict) {
+        let entry = plist_dict(value);
+        let format = entry
+            .iter()
+            .find(|(name, _)| name == "NSStringLocalizedFormatKey")
+            .map(|(_, value)| value.text())
+            .ok_or_else(|| format!("Invalid .stringsdict: '{}' has no NSStringLocalizedFormatKey", key))?;
+
+        // Variables referenced as `%#@name@` from the format string. The first
+        // one decides which plural categories the entry has.
+        let variables: Vec<(&str, Vec<(String, &Element)>)> = entry
+            .iter()
+            .filter(|(_, value)| value.name == "dict")
+            .map(|(name, value)| (name.as_str(), plist_dict(value)))
+            .collect();
+        let first = match variables.first() {
+            Some((name, _)) => *name,
+            None => {
+                entries.push((key, TranslationValue::String(printf_to_named(&format))));
+                continue;
+            }
+        };
+
+        let mut forms = HashMap::new();
+        for category in PLURAL_CATEGORIES {
+            let mut text = format.clone();
+            let mut found = false;
+            for (name, rules) in &variables {
+                let rule = rules
+                    .iter()
+                    .find(|(rule, _)| rule == category)
+                    .or_else(|| rules.iter().find(|(rule, _)| rule == "other"));
+                if let Some((rule, value)) = rule {
+                    found |= *name == first && rule == category;
+                    text = replace_variable(&text, name, &value.text());
+                }
+            }
+            if found {
+                forms.insert(category.to_string(), TranslationValue::String(printf_to_named(&text)));
+            }
+        }
+        entries.push((key, TranslationValue::Nested(forms)));
+    }
+    Ok(entries)
+}
+
+/// Replaces `%#@name@` (or the positional `%1$#@name@`) references in a
+/// `.stringsdict` format string.
+fn replace_variable(format: &str, name: &str, value: &str) -> String {
+    let reference = format!("#@{}@", name);
+    let mut out = String::with_capacity(format.len());
+    let mut rest = format;
+    while let Some(index) = rest.find(&reference) {
+        let before = &rest[..index];
+        let start = before.rfind('%').filter(|&percent| {
+            let position = &before[percent + 1..];
+            position.is_empty() || (position.ends_with('$') && position[..position.len() - 1].chars().all(|c| c.is_ascii_digit()))
+        });
+        match start {
+            Some(percent) => {
+                out.push_str(&before[..percent]);
+                out.push_str(value);
+            }
+            None => {
+                out.push_str(before);
+                out.push_str(&reference);
+            }
+        }
+        rest = &rest[index + reference.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Reads a plist `<dict>` as `(key, value element)` pairs.
+fn plist_dict(dict: &Element) -> Vec<(String, &Element)> {
+    let mut pairs = Vec::new();
+    let mut key = None;
+    for element in dict.elements() {
+        if element.name == "key" {
+            key = Some(element.text());
+        } else if let Some(name) = key.take() {
+            pairs.push((name, element));
+        }
+    }
+    pairs
+}
+
+/// Writes every plural map of a locale as a `.stringsdict` plist.
+///
+/// The plural count is the placeholder named `count`, or the first one, and is
+/// written as an integer (`%1$d`); all other placeholders are objects (`%2$@`).
+pub fn write_stringsdict(map: &HashMap<String, TranslationValue>) -> String {
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n",
+        "<plist version=\"1.0\">\n<dict>\n",
+    ));
+    for (path, value) in flatten_resources(map) {
+        let name = resource_name(&path);
+        let forms = match value {
+            Resource::Plural(forms) => forms,
+            _ => continue,
+        };
+        let texts: Vec<&str> = forms.iter().map(|(_, text)| *text).collect();
+        let positions = printf_positions(&texts);
+        let count = positions
+            .get_key_value("count")
+            .or_else(|| positions.iter().min_by_key(|(_, position)| **position))
+            .map(|(name, position)| (*name, *position));
+        let conversion = |name: &str| if count.map(|(count, _)| count) == Some(name) { 'd' } else { '@' };
+
+        out.push_str(&format!("    <key>{}</key>\n    <dict>\n", escape(&name)));
+        out.push_str(&format!(
+            "        <key>NSStringLocalizedFormatKey</key>\n        <string>%{}$#@value@</string>\n",
+            count.map_or(1, |(_, position)| position)
+        ));
+        out.push_str("        <key>value</key>\n        <dict>\n");
+        out.push_str("            <key>NSStringFormatSpecTypeKey</key>\n            <string>NSStringPluralRuleType</string>\n");
+        out.push_str("            <key>NSStringFormatValueTypeKey</key>\n            <string>d</string>\n");
+        for (category, text) in forms {
+            out.push_str(&format!(
+                "            <key>{}</key>\n            <string>{}</string>\n",
+                category,
+                escape(&named_to_printf(text, &positions, conversion))
+            ));
+        }
+        out.push_str("        </dict>\n    </dict>\n");
+    }
+    out.push_str("</dict>\n</plist>\n");
+    out
+}
@@ -0,0 +1,88 @@
+//! Per-call options accepted by the lookup and formatting methods.
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use crate::markup::DEFAULT_ALLOWED_TAGS;
+
+/// The marker `replace-missing` substitutes for an absent argument; `{name}`
+/// stands for the argument's name.
+pub const DEFAULT_MISSING_MARKER: &str = "⟦{name}⟧";
+
+/// How `formatTranslation` treats arguments that do not match the message.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum FormatMode {
+    /// Fail on a missing or unused argument.
+    Strict,
+    /// Leave `{name}` in the output and ignore extra arguments.
+    #[default]
+    Lenient,
+    /// Substitute the missing-argument marker for absent arguments.
+    ReplaceMissing,
+}
+
+impl FormatMode {
+    pub fn parse(mode: &str) -> Result<FormatMode, String> {
+        match mode {
+            "strict" => Ok(FormatMode::Strict),
+            "lenient" => Ok(FormatMode::Lenient),
+            "replace-missing" => Ok(FormatMode::ReplaceMissing),
+            other => Err(format!("Unknown format mode '{}'", other)),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FormatMode::Strict => "strict",
+            FormatMode::Lenient => "lenient",
+            FormatMode::ReplaceMissing => "replace-missing",
+        }
+    }
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TranslationOptions {
+    /// Disambiguation context: `key#context` is looked up before `key`.
+    pub context: Option<String>,
+    /// Shorthand for `mode: "strict"`.
+    pub strict: bool,
+    /// Overrides the instance's format mode for this call.
+    pub mode: Option<FormatMode>,
+    /// Overrides the instance's missing-argument marker for this call.
+    pub missing_marker: Option<String>,
+    /// HTML-escape arguments and template text other than allowed tags.
+    pub escape: bool,
+    /// Tags kept as markup when escaping, instead of `DEFAULT_ALLOWED_TAGS`.
+    pub allowed_tags: Option<Vec<String>>,
+    /// Format strings as ICU messages, as `formatToParts` does, instead of
+    /// substituting `{name}` placeholders.
+    pub icu: bool,
+}
+
+impl TranslationOptions {
+    /// Reads options from JS, treating `undefined` and `null` as no options.
+    pub fn from_js(value: JsValue) -> Result<TranslationOptions, JsValue> {
+        if value.is_undefined() || value.is_null() {
+            return Ok(TranslationOptions::default());
+        }
+        serde_wasm_bindgen::from_value(value).map_err(JsValue::from)
+    }
+
+    /// The mode this call formats with, given the instance's mode.
+    pub fn format_mode(&self, instance_mode: FormatMode) -> FormatMode {
+        match self.mode {
+            Some(mode) => mode,
+            None if self.strict => FormatMode::Strict,
+            None => instance_mode,
+        }
+    }
+
+    /// Whether `tag` is kept as markup when escaping.
+    pub fn allows_tag(&self, tag: &str) -> bool {
+        match &self.allowed_tags {
+            Some(tags) => tags.iter().any(|allowed| allowed.eq_ignore_ascii_case(tag)),
+            None => DEFAULT_ALLOWED_TAGS.iter().any(|allowed| allowed.eq_ignore_ascii_case(tag)),
+        }
+    }
+}
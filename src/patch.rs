@@ -0,0 +1,81 @@
+//! Diffs between translation trees as JSON-Patch-like operations keyed by
+//! dotted paths.
+//!
+//! A diff compares the string leaves of two trees: keys only in the target
+//! are `add`ed, keys only in the source are `remove`d and keys whose text
+//! differs are `replace`d. Removals come first, so a string that became a
+//! nested map (or the reverse) is removed before its replacement is added.
+
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use crate::batch::BatchOp;
+use crate::helpers::map_leaves;
+use crate::TranslationValue;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+    Add {
+        path: String,
+        value: TranslationValue,
+    },
+    Remove {
+        path: String,
+    },
+    Replace {
+        path: String,
+        value: TranslationValue,
+        #[serde(rename = "oldValue", default, skip_serializing_if = "Option::is_none")]
+        old_value: Option<TranslationValue>,
+    },
+}
+
+/// The operations that turn `source` into `target`.
+pub fn diff_maps(
+    source: &HashMap<String, TranslationValue>,
+    target: &HashMap<String, TranslationValue>,
+) -> Vec<PatchOp> {
+    let source = leaves(source);
+    let target = leaves(target);
+
+    let mut ops: Vec<PatchOp> = source
+        .keys()
+        .filter(|path| !target.contains_key(*path))
+        .map(|path| PatchOp::Remove { path: path.clone() })
+        .collect();
+    for (path, text) in &target {
+        match source.get(path) {
+            None => ops.push(PatchOp::Add {
+                path: path.clone(),
+                value: TranslationValue::String(text.to_string()),
+            }),
+            Some(old) if old != text => ops.push(PatchOp::Replace {
+                path: path.clone(),
+                value: TranslationValue::String(text.to_string()),
+                old_value: Some(TranslationValue::String(old.to_string())),
+            }),
+            Some(_) => {}
+        }
+    }
+    ops
+}
+
+/// Turns patch operations on `locale` into batch operations: `add` and
+/// `replace` write the value, `remove` deletes the key, which must exist.
+pub fn patch_to_ops(locale: &str, patch: Vec<PatchOp>) -> Vec<BatchOp> {
+    patch
+        .into_iter()
+        .map(|op| match op {
+            PatchOp::Add { path, value } | PatchOp::Replace { path, value, .. } => BatchOp::Set {
+                locale: locale.to_string(),
+                key: path,
+                value,
+            },
+            PatchOp::Remove { path } => BatchOp::Delete { locale: locale.to_string(), key: path },
+        })
+        .collect()
+}
+
+fn leaves(map: &HashMap<String, TranslationValue>) -> BTreeMap<String, &str> {
+    map_leaves(map).into_iter().collect()
+}
@@ -0,0 +1,212 @@
+//! Placeholder introspection: which arguments a message expects, and of
+//! which kind, parsed from its ICU MessageFormat or Fluent pattern.
+
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::fluent;
+use crate::helpers::{FormatArg, PLURAL_CATEGORIES};
+use crate::icu::{self, ArgumentKind};
+use crate::options::FormatMode;
+use crate::TranslationValue;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaceholderKind {
+    Plain,
+    Number,
+    Date,
+    Plural,
+    Select,
+}
+
+#[derive(Serialize, Clone, PartialEq, Debug)]
+pub struct Placeholder {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: PlaceholderKind,
+}
+
+/// Placeholders of a stored value, in order of first appearance. Plural maps
+/// and other nested values report the union over their strings.
+pub fn placeholders(value: &TranslationValue, is_fluent: bool) -> Result<Vec<Placeholder>, String> {
+    let mut out = Vec::new();
+    collect(value, is_fluent, &mut out)?;
+    Ok(out)
+}
+
+fn collect(value: &TranslationValue, is_fluent: bool, out: &mut Vec<Placeholder>) -> Result<(), String> {
+    match value {
+        TranslationValue::String(text) if is_fluent => {
+            fluent_nodes(&fluent::parse_pattern(text)?, out);
+        }
+        TranslationValue::String(text) => {
+            let nodes = icu::parse(text).map_err(|e| format!("Invalid message: {}", e))?;
+            for argument in icu::arguments(&nodes) {
+                let kind = match argument.kind {
+                    ArgumentKind::Plain => PlaceholderKind::Plain,
+                    ArgumentKind::Number(_) => PlaceholderKind::Number,
+                    ArgumentKind::Date(_) | ArgumentKind::Time(_) => PlaceholderKind::Date,
+                    ArgumentKind::Plural { .. } => PlaceholderKind::Plural,
+                    ArgumentKind::Select(_) => PlaceholderKind::Select,
+                };
+                add(out, &argument.name, kind);
+            }
+        }
+        TranslationValue::Nested(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            // Keep plural forms in CLDR order so `other` does not come first.
+            keys.sort_by_key(|key| (PLURAL_CATEGORIES.iter().position(|c| c == key), key.as_str()));
+            for key in keys {
+                collect(&map[key], is_fluent, out)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Placeholders of a value formatted by plain `{name}` substitution, the
+/// grammar `formatTranslation` uses without `icu`. Any brace pair around a
+/// name without whitespace counts; other braces are literal text.
+fn substitutions(value: &TranslationValue, out: &mut Vec<Placeholder>) {
+    match value {
+        TranslationValue::String(text) => {
+            let mut rest = text.as_str();
+            while let Some(open) = rest.find('{') {
+                rest = &rest[open + 1..];
+                let end = match rest.find(&['{', '}'][..]) {
+                    Some(end) => end,
+                    None => break,
+                };
+                let name = &rest[..end];
+                if rest[end..].starts_with('}') && !name.is_empty() && !name.contains(char::is_whitespace) {
+                    add(out, name, PlaceholderKind::Plain);
+                }
+                rest = &rest[end..];
+            }
+        }
+        TranslationValue::Nested(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort_by_key(|key| (PLURAL_CATEGORIES.iter().position(|c| c == key), key.as_str()));
+            for key in keys {
+                substitutions(&map[key], out);
+            }
+        }
+    }
+}
+
+/// Records `name`, upgrading a plain placeholder to a more specific kind when
+/// the same argument is also used as, say, a plural selector.
+fn add(out: &mut Vec<Placeholder>, name: &str, kind: PlaceholderKind) {
+    match out.iter_mut().find(|placeholder| placeholder.name == name) {
+        Some(existing) => {
+            if existing.kind == PlaceholderKind::Plain {
+                existing.kind = kind;
+            }
+        }
+        None => out.push(Placeholder { name: name.to_string(), kind }),
+    }
+}
+
+fn fluent_nodes(nodes: &[fluent::Node], out: &mut Vec<Placeholder>) {
+    for node in nodes {
+        if let fluent::Node::Placeable(expr) = node {
+            fluent_expr(expr, out);
+        }
+    }
+}
+
+fn fluent_expr(expr: &fluent::Expr, out: &mut Vec<Placeholder>) {
+    use fluent::Expr;
+
+    match expr {
+        Expr::Var(name) => add(out, name, PlaceholderKind::Plain),
+        Expr::Call { name, args } => {
+            let kind = match name.as_str() {
+                "NUMBER" => PlaceholderKind::Number,
+                "DATETIME" => PlaceholderKind::Date,
+                _ => PlaceholderKind::Plain,
+            };
+            for arg in args {
+                match arg {
+                    Expr::Var(var) => add(out, var, kind),
+                    other => fluent_expr(other, out),
+                }
+            }
+        }
+        Expr::Term { args, .. } => {
+            for (_, arg) in args {
+                fluent_expr(arg, out);
+            }
+        }
+        Expr::Select { selector, variants } => {
+            let plural = variants
+                .iter()
+                .all(|variant| PLURAL_CATEGORIES.contains(&variant.key.as_str()) || variant.key.parse::<f64>().is_ok());
+            let kind = if plural { PlaceholderKind::Plural } else { PlaceholderKind::Select };
+            match selector.as_ref() {
+                Expr::Var(var) => add(out, var, kind),
+                other => fluent_expr(other, out),
+            }
+            for variant in variants {
+                fluent_nodes(&variant.pattern, out);
+            }
+        }
+        Expr::Str(_) | Expr::Num(..) | Expr::Message { .. } => {}
+    }
+}
+
+/// Applies `mode` to the arguments of a format call. Strict mode fails on the
+/// first missing or unused argument; replace-missing adds `marker` (with
+/// `{name}` replaced by the argument name) for every missing one. Arguments
+/// are read with the grammar the call formats with: ICU or Fluent patterns
+/// when `icu` is set or the value is Fluent, `{name}` substitution otherwise.
+/// Values that do not parse are left to the formatter in replace-missing mode.
+pub fn check_arguments(
+    value: &TranslationValue,
+    is_fluent: bool,
+    icu: bool,
+    mode: FormatMode,
+    marker: &str,
+    args: &mut HashMap<String, FormatArg>,
+) -> Result<(), String> {
+    if mode == FormatMode::Lenient {
+        return Ok(());
+    }
+    let parsed = if is_fluent || icu {
+        placeholders(value, is_fluent)
+    } else {
+        let mut out = Vec::new();
+        substitutions(value, &mut out);
+        Ok(out)
+    };
+    let expected = match parsed {
+        Ok(expected) => expected,
+        Err(e) if mode == FormatMode::Strict => return Err(e),
+        Err(_) => return Ok(()),
+    };
+
+    let mut missing = expected.iter().filter(|placeholder| !args.contains_key(&placeholder.name));
+    if mode == FormatMode::ReplaceMissing {
+        let markers: Vec<(String, FormatArg)> = missing
+            .map(|placeholder| {
+                let text = marker.replace("{name}", &placeholder.name);
+                (placeholder.name.clone(), FormatArg::String(text))
+            })
+            .collect();
+        args.extend(markers);
+        return Ok(());
+    }
+
+    if let Some(placeholder) = missing.next() {
+        return Err(format!("Missing argument '{}'", placeholder.name));
+    }
+    let mut unused: Vec<&String> = args
+        .keys()
+        .filter(|name| !expected.iter().any(|placeholder| &placeholder.name == *name))
+        .collect();
+    unused.sort();
+    match unused.first() {
+        Some(name) => Err(format!("Unused argument '{}'", name)),
+        None => Ok(()),
+    }
+}
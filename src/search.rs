@@ -0,0 +1,112 @@
+//! Enumeration and search of the string keys of a locale.
+//!
+//! Both walk the locale's tree and report dotted paths in sorted order, so
+//! results are stable across calls regardless of map iteration order.
+
+use std::collections::HashMap;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use crate::helpers::{canonical_key, map_leaves};
+use crate::TranslationValue;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchScope {
+    Keys,
+    Values,
+    #[default]
+    All,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchOptions {
+    /// Treat the query as a regular expression instead of a substring.
+    pub regex: bool,
+    pub ignore_case: bool,
+    /// Whether keys, values or both are matched.
+    pub scope: SearchScope,
+}
+
+impl SearchOptions {
+    /// Reads options from JS, treating `undefined` and `null` as no options.
+    pub fn from_js(value: JsValue) -> Result<SearchOptions, JsValue> {
+        if value.is_undefined() || value.is_null() {
+            return Ok(SearchOptions::default());
+        }
+        serde_wasm_bindgen::from_value(value).map_err(JsValue::from)
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SearchMatch {
+    pub key: String,
+    pub value: String,
+}
+
+/// Every string key of `map`, sorted. With a `prefix`, only the keys at or
+/// below that path: `menu` matches `menu` and `menu.open`, not `menuBar`.
+pub fn list_keys(map: &HashMap<String, TranslationValue>, prefix: Option<&str>) -> Vec<String> {
+    let prefix = prefix.map(canonical_key);
+    let prefix = prefix.as_deref().map(|prefix| prefix.strip_suffix('.').unwrap_or(prefix)).filter(|prefix| !prefix.is_empty());
+    let mut keys: Vec<String> = map_leaves(map)
+        .into_iter()
+        .map(|(key, _)| key)
+        .filter(|key| match prefix {
+            Some(prefix) => key == prefix || key.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('.')),
+            None => true,
+        })
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// Number of string keys in `map`.
+pub fn count_keys(map: &HashMap<String, TranslationValue>) -> usize {
+    map_leaves(map).len()
+}
+
+/// The keys of `map` whose path or text matches `query`, sorted by key.
+pub fn search(map: &HashMap<String, TranslationValue>, query: &str, options: &SearchOptions) -> Result<Vec<SearchMatch>, String> {
+    let matcher = Matcher::new(query, options)?;
+    let mut matches: Vec<SearchMatch> = map_leaves(map)
+        .into_iter()
+        .filter(|(key, value)| match options.scope {
+            SearchScope::Keys => matcher.is_match(key),
+            SearchScope::Values => matcher.is_match(value),
+            SearchScope::All => matcher.is_match(key) || matcher.is_match(value),
+        })
+        .map(|(key, value)| SearchMatch { key, value: value.to_string() })
+        .collect();
+    matches.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(matches)
+}
+
+enum Matcher {
+    Regex(Regex),
+    Substring { query: String, ignore_case: bool },
+}
+
+impl Matcher {
+    fn new(query: &str, options: &SearchOptions) -> Result<Matcher, String> {
+        if options.regex {
+            let regex = RegexBuilder::new(query)
+                .case_insensitive(options.ignore_case)
+                .build()
+                .map_err(|e| format!("Invalid regex: {}", e))?;
+            return Ok(Matcher::Regex(regex));
+        }
+        let query = if options.ignore_case { query.to_lowercase() } else { query.to_string() };
+        Ok(Matcher::Substring { query, ignore_case: options.ignore_case })
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        match self {
+            Matcher::Regex(regex) => regex.is_match(text),
+            Matcher::Substring { query, ignore_case: true } => text.to_lowercase().contains(query.as_str()),
+            Matcher::Substring { query, ignore_case: false } => text.contains(query.as_str()),
+        }
+    }
+}
+
@@ -0,0 +1,91 @@
+//! Versioned snapshots of a whole `I18n` instance.
+//!
+//! The JSON form is `{ "version": 1, "settings": { ... }, "translations": { ... },
+//! "metadata": { ... } }`. The binary form is the magic `WI1S`, a version byte,
+//! the settings and the key metadata as length-prefixed JSON objects and then
+//! an embedded binary bundle (see `bundle.rs`) holding the translation trees as
+//! they are, empty maps and literal dotted keys included.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use serde::{Deserialize, Serialize};
+use crate::bundle::{decode_bundle, encode_bundle, write_varint, BundleLocale, Reader};
+use crate::metadata::KeyMetadata;
+use crate::options::FormatMode;
+use crate::TranslationValue;
+
+pub const SNAPSHOT_MAGIC: &[u8; 4] = b"WI1S";
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Key metadata by locale and dotted key.
+pub type MetadataMap = HashMap<String, HashMap<String, KeyMetadata>>;
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    pub key_index: bool,
+    /// Top-level keys imported from Fluent resources, per locale.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub fluent_keys: BTreeMap<String, BTreeSet<String>>,
+    pub format_mode: FormatMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub missing_marker: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locale: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    #[serde(default)]
+    pub settings: Settings,
+    pub translations: HashMap<String, HashMap<String, TranslationValue>>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub metadata: MetadataMap,
+}
+
+impl Snapshot {
+    pub fn from_json(json: &str) -> Result<Snapshot, String> {
+        let snapshot: Snapshot =
+            serde_json::from_str(json).map_err(|e| format!("Invalid snapshot JSON: {}", e))?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(format!("Unsupported snapshot version {}", snapshot.version));
+        }
+        Ok(snapshot)
+    }
+
+    pub fn to_binary(&self) -> Vec<u8> {
+        let settings = serde_json::to_vec(&self.settings).unwrap_or_default();
+        let metadata = serde_json::to_vec(&self.metadata).unwrap_or_default();
+
+        let mut out = Vec::new();
+        out.extend_from_slice(SNAPSHOT_MAGIC);
+        out.push(SNAPSHOT_VERSION as u8);
+        write_varint(&mut out, settings.len() as u32);
+        out.extend_from_slice(&settings);
+        write_varint(&mut out, metadata.len() as u32);
+        out.extend_from_slice(&metadata);
+        out.extend_from_slice(&encode_bundle(&self.translations));
+        out
+    }
+}
+
+/// Decodes a binary snapshot into its settings, key metadata and translation
+/// trees.
+pub fn decode_snapshot_binary(bytes: &[u8]) -> Result<(Settings, MetadataMap, Vec<BundleLocale>), String> {
+    let mut reader = Reader::new(bytes);
+    if reader.take(SNAPSHOT_MAGIC.len()).ok() != Some(&SNAPSHOT_MAGIC[..]) {
+        return Err("Invalid snapshot: bad magic".to_string());
+    }
+    let version = reader.take(1)?[0] as u32;
+    if version != SNAPSHOT_VERSION {
+        return Err(format!("Unsupported snapshot version {}", version));
+    }
+
+    let len = reader.varint()? as usize;
+    let settings = serde_json::from_slice(reader.take(len)?)
+        .map_err(|e| format!("Invalid snapshot settings: {}", e))?;
+    let len = reader.varint()? as usize;
+    let metadata =
+        serde_json::from_slice(reader.take(len)?).map_err(|e| format!("Invalid snapshot metadata: {}", e))?;
+    Ok((settings, metadata, decode_bundle(reader.remaining())?))
+}
@@ -0,0 +1,203 @@
+//! Validation of every locale against a source locale.
+//!
+//! Keys are compared as dotted paths to string leaves. Plural maps (keyed by
+//! CLDR category) are compared as a whole, since locales legitimately use
+//! different categories; their placeholders are the union over all forms.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use serde::Serialize;
+use crate::helpers::{collect_leaves, escape_segment, is_plural_map, join_key};
+use crate::icu;
+use crate::TranslationValue;
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+    pub source_locale: String,
+    pub valid: bool,
+    pub locales: BTreeMap<String, LocaleReport>,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LocaleReport {
+    /// Keys present in the source locale but not in this one.
+    pub missing: Vec<String>,
+    /// Keys present in this locale but not in the source locale.
+    pub orphans: Vec<String>,
+    pub shape_conflicts: Vec<ShapeConflict>,
+    pub placeholder_mismatches: Vec<PlaceholderMismatch>,
+    pub syntax_errors: Vec<SyntaxError>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShapeConflict {
+    pub key: String,
+    /// `"string"` or `"nested"`.
+    pub source: &'static str,
+    pub target: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaceholderMismatch {
+    pub key: String,
+    /// Placeholders used in the source but not in this locale.
+    pub missing: Vec<String>,
+    /// Placeholders used in this locale but not in the source.
+    pub extra: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxError {
+    pub key: String,
+    pub message: String,
+}
+
+impl LocaleReport {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty()
+            && self.orphans.is_empty()
+            && self.shape_conflicts.is_empty()
+            && self.placeholder_mismatches.is_empty()
+            && self.syntax_errors.is_empty()
+    }
+}
+
+/// Validates `locales` against `source`. Top-level keys for which `is_fluent`
+/// returns `true` for `(locale, key)` hold Fluent messages and skip the ICU
+/// syntax and placeholder checks.
+pub fn validate_locales(
+    source_locale: &str,
+    locales: &HashMap<String, HashMap<String, TranslationValue>>,
+    is_fluent: impl Fn(&str, &str) -> bool,
+) -> Result<ValidationReport, String> {
+    let source = locales.get(source_locale).ok_or("Locale not found")?;
+    let mut report = ValidationReport {
+        source_locale: source_locale.to_string(),
+        ..ValidationReport::default()
+    };
+
+    let source_placeholders = placeholders_by_key(source, |key| is_fluent(source_locale, key), &mut Vec::new());
+    for (locale, map) in locales {
+        let mut locale_report = LocaleReport::default();
+        let placeholders = placeholders_by_key(map, |key| is_fluent(locale, key), &mut locale_report.syntax_errors);
+
+        if locale != source_locale {
+            compare_maps("", source, map, &mut locale_report);
+            for (key, expected) in &source_placeholders {
+                if let Some(actual) = placeholders.get(key) {
+                    if expected != actual {
+                        locale_report.placeholder_mismatches.push(PlaceholderMismatch {
+                            key: key.clone(),
+                            missing: expected.difference(actual).cloned().collect(),
+                            extra: actual.difference(expected).cloned().collect(),
+                        });
+                    }
+                }
+            }
+        }
+
+        locale_report.missing.sort();
+        locale_report.orphans.sort();
+        locale_report.shape_conflicts.sort_by(|a, b| a.key.cmp(&b.key));
+        locale_report.placeholder_mismatches.sort_by(|a, b| a.key.cmp(&b.key));
+        locale_report.syntax_errors.sort_by(|a, b| a.key.cmp(&b.key));
+        report.locales.insert(locale.clone(), locale_report);
+    }
+    report.valid = report.locales.values().all(LocaleReport::is_empty);
+    Ok(report)
+}
+
+fn leaf_keys(path: &str, value: &TranslationValue, out: &mut Vec<String>) {
+    match value {
+        TranslationValue::Nested(map) if is_plural_map(map) => out.push(path.to_string()),
+        value => {
+            let mut leaves = Vec::new();
+            collect_leaves(path, value, &mut leaves);
+            out.extend(leaves.into_iter().map(|(key, _)| key));
+        }
+    }
+}
+
+fn shape(value: &TranslationValue) -> &'static str {
+    match value {
+        TranslationValue::String(_) => "string",
+        TranslationValue::Nested(_) => "nested",
+    }
+}
+
+fn compare_maps(
+    prefix: &str,
+    source: &HashMap<String, TranslationValue>,
+    target: &HashMap<String, TranslationValue>,
+    report: &mut LocaleReport,
+) {
+    for (key, source_value) in source {
+        let path = join_key(prefix, key);
+        match (source_value, target.get(key)) {
+            (_, None) => leaf_keys(&path, source_value, &mut report.missing),
+            (TranslationValue::String(_), Some(TranslationValue::String(_))) => {}
+            (TranslationValue::Nested(source_map), Some(TranslationValue::Nested(target_map))) => {
+                // Plural maps are compared as a whole.
+                if !(is_plural_map(source_map) && is_plural_map(target_map)) {
+                    compare_maps(&path, source_map, target_map, report);
+                }
+            }
+            (source_value, Some(target_value)) => report.shape_conflicts.push(ShapeConflict {
+                key: path,
+                source: shape(source_value),
+                target: shape(target_value),
+            }),
+        }
+    }
+    for (key, target_value) in target {
+        if !source.contains_key(key) {
+            leaf_keys(&join_key(prefix, key), target_value, &mut report.orphans);
+        }
+    }
+}
+
+/// Parses every string of a locale, returning the placeholder names per key
+/// (plural maps as a single key) and recording syntax errors. Top-level keys
+/// for which `is_fluent` returns `true` are skipped.
+fn placeholders_by_key(
+    map: &HashMap<String, TranslationValue>,
+    is_fluent: impl Fn(&str) -> bool,
+    errors: &mut Vec<SyntaxError>,
+) -> HashMap<String, BTreeSet<String>> {
+    fn walk(
+        path: &str,
+        value: &TranslationValue,
+        plural_key: Option<&str>,
+        out: &mut HashMap<String, BTreeSet<String>>,
+        errors: &mut Vec<SyntaxError>,
+    ) {
+        match value {
+            TranslationValue::String(text) => match icu::parse(text) {
+                Ok(nodes) => {
+                    let names = out.entry(plural_key.unwrap_or(path).to_string()).or_default();
+                    names.extend(icu::arguments(&nodes).into_iter().map(|argument| argument.name.clone()));
+                }
+                Err(error) => errors.push(SyntaxError {
+                    key: path.to_string(),
+                    message: error.to_string(),
+                }),
+            },
+            TranslationValue::Nested(map) => {
+                let plural_key = plural_key.or_else(|| Some(path).filter(|_| is_plural_map(map)));
+                for (key, child) in map {
+                    walk(&join_key(path, key), child, plural_key, out, errors);
+                }
+            }
+        }
+    }
+
+    let mut out = HashMap::new();
+    for (key, value) in map.iter().filter(|(key, _)| !is_fluent(key)) {
+        walk(&escape_segment(key), value, None, &mut out, errors);
+    }
+    out
+}
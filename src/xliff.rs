@@ -0,0 +1,269 @@
+//! XLIFF 1.2 and 2.0 import and export.
+//!
+//! A unit's `id` (or `resname` in 1.2) is used as its dotted key, so units map
+//! one-to-one onto string leaves of a locale's translation tree. Units without
+//! an id are skipped. Notes carry the key's description and 1.2's `maxwidth`
+//! its maximum length.
+
+use std::collections::{BTreeMap, HashMap};
+use serde::Serialize;
+use crate::helpers::map_leaves;
+use crate::metadata::KeyMetadata;
+use crate::xml::{escape, parse, Element, XmlNode};
+use crate::TranslationValue;
+
+pub struct XliffUnit {
+    pub id: String,
+    pub source: String,
+    pub target: Option<String>,
+    pub state: Option<String>,
+    pub notes: Vec<String>,
+    pub max_length: Option<u32>,
+}
+
+impl XliffUnit {
+    pub fn metadata(&self) -> KeyMetadata {
+        KeyMetadata {
+            description: Some(self.notes.join("\n")).filter(|notes| !notes.is_empty()),
+            max_length: self.max_length,
+            ..KeyMetadata::default()
+        }
+    }
+}
+
+pub struct XliffDocument {
+    pub source_locale: Option<String>,
+    pub target_locale: Option<String>,
+    pub units: Vec<XliffUnit>,
+    /// Units dropped because they have no `id` (or `resname`).
+    pub unnamed: usize,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct XliffImportReport {
+    pub imported: usize,
+    pub skipped: usize,
+    pub source_locale: Option<String>,
+    pub target_locale: String,
+    pub states: BTreeMap<String, usize>,
+    pub notes: BTreeMap<String, Vec<String>>,
+}
+
+pub fn parse_xliff(text: &str) -> Result<XliffDocument, String> {
+    let root = parse(text)?;
+    if root.name != "xliff" {
+        return Err(format!("Invalid XLIFF: unexpected root element '{}'", root.name));
+    }
+
+    let mut document = XliffDocument {
+        source_locale: root.attr("srcLang").map(str::to_string),
+        target_locale: root.attr("trgLang").map(str::to_string),
+        units: Vec::new(),
+        unnamed: 0,
+    };
+    match root.attr("version") {
+        Some(version) if version.starts_with('2') => {
+            for file in root.children_named("file") {
+                collect_units_v2(file, &mut document);
+            }
+        }
+        Some(version) if version.starts_with('1') => {
+            for file in root.children_named("file") {
+                if document.source_locale.is_none() {
+                    document.source_locale = file.attr("source-language").map(str::to_string);
+                }
+                if document.target_locale.is_none() {
+                    document.target_locale = file.attr("target-language").map(str::to_string);
+                }
+                if let Some(body) = file.child("body") {
+                    collect_units_v1(body, &mut document);
+                }
+            }
+        }
+        version => {
+            return Err(format!("Unsupported XLIFF version {}", version.unwrap_or("(missing)")));
+        }
+    }
+    Ok(document)
+}
+
+fn collect_units_v1(parent: &Element, document: &mut XliffDocument) {
+    for element in parent.elements() {
+        match element.name.as_str() {
+            "group" => collect_units_v1(element, document),
+            "trans-unit" => {
+                let Some(id) = element.attr("resname").or_else(|| element.attr("id")).filter(|id| !id.is_empty()) else {
+                    document.unnamed += 1;
+                    continue;
+                };
+                let target = element.child("target");
+                document.units.push(XliffUnit {
+                    id: id.to_string(),
+                    source: element.child("source").map(inline_text).unwrap_or_default(),
+                    target: target.map(inline_text),
+                    state: target.and_then(|t| t.attr("state")).map(str::to_string),
+                    notes: element.children_named("note").map(Element::text).collect(),
+                    max_length: element
+                        .attr("maxwidth")
+                        .filter(|_| matches!(element.attr("size-unit"), None | Some("char")))
+                        .and_then(|width| width.trim().parse().ok()),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_units_v2(parent: &Element, document: &mut XliffDocument) {
+    for element in parent.elements() {
+        match element.name.as_str() {
+            "group" => collect_units_v2(element, document),
+            "unit" => {
+                let Some(id) = element.attr("id").filter(|id| !id.is_empty()) else {
+                    document.unnamed += 1;
+                    continue;
+                };
+                let mut source = String::new();
+                let mut target: Option<String> = None;
+                let mut state = None;
+                for segment in element.elements().filter(|e| e.name == "segment" || e.name == "ignorable") {
+                    if state.is_none() {
+                        state = segment.attr("state").map(str::to_string);
+                    }
+                    if let Some(s) = segment.child("source") {
+                        source.push_str(&inline_text(s));
+                    }
+                    if let Some(t) = segment.child("target") {
+                        target.get_or_insert_with(String::new).push_str(&inline_text(t));
+                    }
+                }
+                let notes = element
+                    .child("notes")
+                    .map(|notes| notes.children_named("note").map(Element::text).collect())
+                    .unwrap_or_default();
+                document.units.push(XliffUnit {
+                    id: id.to_string(),
+                    source,
+                    target,
+                    state,
+                    notes,
+                    max_length: None,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Flattens inline markup; placeholder elements become their `equiv` text,
+/// or `{id}` when they have none.
+fn inline_text(element: &Element) -> String {
+    let mut out = String::new();
+    for child in &element.children {
+        match child {
+            XmlNode::Text(text) => out.push_str(text),
+            XmlNode::Element(inner) => match inner.name.as_str() {
+                "x" | "ph" | "bx" | "ex" | "sc" | "ec" => {
+                    match inner.attr("equiv-text").or_else(|| inner.attr("equiv")).or_else(|| inner.attr("disp")) {
+                        Some(equiv) => out.push_str(equiv),
+                        None => out.push_str(&format!("{{{}}}", inner.attr("id").unwrap_or_default())),
+                    }
+                }
+                _ => out.push_str(&inline_text(inner)),
+            },
+        }
+    }
+    out
+}
+
+/// Writes a source/target locale pair as an XLIFF document.
+pub fn write_xliff(
+    version: &str,
+    source_locale: &str,
+    target_locale: &str,
+    source: &HashMap<String, TranslationValue>,
+    target: Option<&HashMap<String, TranslationValue>>,
+    metadata: Option<&HashMap<String, KeyMetadata>>,
+) -> Result<String, String> {
+    let metadata_for = |id: &str| metadata.and_then(|metadata| metadata.get(id));
+    let units = units_for(source, target);
+    let (src, trg) = (escape(source_locale), escape(target_locale));
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+
+    match version {
+        "1.2" => {
+            out.push_str("<xliff version=\"1.2\" xmlns=\"urn:oasis:names:tc:xliff:document:1.2\">\n");
+            out.push_str(&format!(
+                "  <file source-language=\"{}\" target-language=\"{}\" datatype=\"plaintext\" original=\"wasm-i18n\">\n    <body>\n",
+                src, trg
+            ));
+            for (id, source, target) in units {
+                let metadata = metadata_for(&id);
+                match metadata.and_then(|metadata| metadata.max_length) {
+                    Some(max_length) => out.push_str(&format!(
+                        "      <trans-unit id=\"{}\" maxwidth=\"{}\" size-unit=\"char\">\n",
+                        escape(&id),
+                        max_length
+                    )),
+                    None => out.push_str(&format!("      <trans-unit id=\"{}\">\n", escape(&id))),
+                }
+                out.push_str(&format!("        <source>{}</source>\n", escape(source)));
+                match target {
+                    Some(target) => out.push_str(&format!(
+                        "        <target state=\"translated\">{}</target>\n",
+                        escape(target)
+                    )),
+                    None => out.push_str("        <target state=\"needs-translation\"></target>\n"),
+                }
+                if let Some(description) = metadata.and_then(|metadata| metadata.description.as_deref()) {
+                    out.push_str(&format!("        <note>{}</note>\n", escape(description)));
+                }
+                out.push_str("      </trans-unit>\n");
+            }
+            out.push_str("    </body>\n  </file>\n</xliff>\n");
+        }
+        "2.0" => {
+            out.push_str(&format!(
+                "<xliff version=\"2.0\" xmlns=\"urn:oasis:names:tc:xliff:document:2.0\" srcLang=\"{}\" trgLang=\"{}\">\n",
+                src, trg
+            ));
+            out.push_str("  <file id=\"wasm-i18n\">\n");
+            for (id, source, target) in units {
+                out.push_str(&format!("    <unit id=\"{}\">\n", escape(&id)));
+                if let Some(description) = metadata_for(&id).and_then(|metadata| metadata.description.as_deref()) {
+                    out.push_str(&format!("      <notes><note>{}</note></notes>\n", escape(description)));
+                }
+                let state = if target.is_some() { "translated" } else { "initial" };
+                out.push_str(&format!("      <segment state=\"{}\">\n", state));
+                out.push_str(&format!("        <source>{}</source>\n", escape(source)));
+                if let Some(target) = target {
+                    out.push_str(&format!("        <target>{}</target>\n", escape(target)));
+                }
+                out.push_str("      </segment>\n    </unit>\n");
+            }
+            out.push_str("  </file>\n</xliff>\n");
+        }
+        _ => return Err(format!("Unsupported XLIFF version {}", version)),
+    }
+    Ok(out)
+}
+
+/// Pairs every source leaf with the target leaf at the same key, sorted by key.
+fn units_for<'a>(
+    source: &'a HashMap<String, TranslationValue>,
+    target: Option<&'a HashMap<String, TranslationValue>>,
+) -> Vec<(String, &'a str, Option<&'a str>)> {
+    let target_leaves: HashMap<String, &str> = target.map(map_leaves).unwrap_or_default().into_iter().collect();
+
+    let mut source_leaves = map_leaves(source);
+    source_leaves.sort();
+
+    source_leaves
+        .into_iter()
+        .map(|(key, text)| {
+            let target = target_leaves.get(&key).copied();
+            (key, text, target)
+        })
+        .collect()
+}
@@ -0,0 +1,160 @@
+//! A minimal XML tree on top of `xmlparser`, shared by the XML-based formats.
+//!
+//! Namespace prefixes are dropped: elements and attributes are matched by
+//! their local name only.
+
+use xmlparser::{ElementEnd, Token, Tokenizer};
+
+#[derive(Debug, Default)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<XmlNode>,
+}
+
+#[derive(Debug)]
+pub enum XmlNode {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|child| match child {
+            XmlNode::Element(element) => Some(element),
+            XmlNode::Text(_) => None,
+        })
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.elements().find(|element| element.name == name)
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.elements().filter(move |element| element.name == name)
+    }
+
+    /// The concatenated text of this element and all of its descendants.
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        self.collect_text(&mut out);
+        out
+    }
+
+    fn collect_text(&self, out: &mut String) {
+        for child in &self.children {
+            match child {
+                XmlNode::Text(text) => out.push_str(text),
+                XmlNode::Element(element) => element.collect_text(out),
+            }
+        }
+    }
+}
+
+/// Parses a document and returns its root element.
+pub fn parse(text: &str) -> Result<Element, String> {
+    let error = |e: xmlparser::Error| format!("XML parse error: {}", e);
+    let mut stack: Vec<Element> = vec![Element::default()];
+
+    for token in Tokenizer::from(text) {
+        match token.map_err(error)? {
+            Token::ElementStart { local, .. } => stack.push(Element {
+                name: local.to_string(),
+                ..Element::default()
+            }),
+            Token::Attribute { local, value, .. } => {
+                let element = stack.last_mut().ok_or("XML parse error: attribute outside of an element")?;
+                element.attributes.push((local.to_string(), unescape(value.as_str())?));
+            }
+            Token::ElementEnd { end, .. } => match end {
+                ElementEnd::Open => {}
+                ElementEnd::Close(_, local) => {
+                    let element = stack.pop().filter(|e| e.name == local.as_str()).ok_or_else(|| {
+                        format!("XML parse error: unexpected closing tag '{}'", local.as_str())
+                    })?;
+                    push_child(&mut stack, XmlNode::Element(element))?;
+                }
+                ElementEnd::Empty => {
+                    let element = stack.pop().ok_or("XML parse error: unbalanced tags")?;
+                    push_child(&mut stack, XmlNode::Element(element))?;
+                }
+            },
+            Token::Text { text } => push_child(&mut stack, XmlNode::Text(unescape(text.as_str())?))?,
+            Token::Cdata { text, .. } => push_child(&mut stack, XmlNode::Text(text.to_string()))?,
+            _ => {}
+        }
+    }
+
+    let document = stack.pop().filter(|_| stack.is_empty()).ok_or("XML parse error: unclosed element")?;
+    document
+        .children
+        .into_iter()
+        .find_map(|child| match child {
+            XmlNode::Element(element) => Some(element),
+            XmlNode::Text(_) => None,
+        })
+        .ok_or_else(|| "XML parse error: no root element".to_string())
+}
+
+fn push_child(stack: &mut [Element], node: XmlNode) -> Result<(), String> {
+    let parent = stack.last_mut().ok_or("XML parse error: unbalanced tags")?;
+    parent.children.push(node);
+    Ok(())
+}
+
+fn unescape(s: &str) -> Result<String, String> {
+    if !s.contains('&') {
+        return Ok(s.to_string());
+    }
+
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find(';')
+            .ok_or_else(|| "XML parse error: unterminated entity".to_string())?;
+        let entity = &rest[start + 1..start + end];
+        let c = match entity {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse()))
+                .and_then(|code| code.ok())
+                .and_then(char::from_u32)
+                .ok_or_else(|| format!("XML parse error: unknown entity '&{};'", entity))?,
+        };
+        out.push(c);
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Escapes text for use in element content or attribute values.
+pub fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
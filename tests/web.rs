@@ -5,7 +5,7 @@
 use serde_json::json;
 use serde_wasm_bindgen::from_value;
 use std::collections::HashMap;
-use wasm_bindgen::JsValue;
+use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_test::*;
 use wasm_i18n::*;
 use web_sys::console;
@@ -210,3 +210,1153 @@ fn test_performance() {
     let duration = end - start;
     console::log_1(&format!("Time to format 100000 translations: {} ms", duration).into());
 }
+
+#[wasm_bindgen_test]
+fn test_key_index() {
+    let i18n = I18n::new();
+    i18n.set_key_index_enabled(true);
+    assert!(i18n.key_index_enabled());
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({
+            "user": { "greeting": "Hello, {username}!", "bye": "Bye" }
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+
+    let greeting: String = from_value(i18n.get_translation("en", "user.greeting").unwrap()).unwrap();
+    assert_eq!(greeting, "Hello, {username}!");
+
+    i18n.update_translation("en", "user.greeting", JsValue::from_str("Hi, {username}!")).unwrap();
+    let mut args = HashMap::new();
+    args.insert("username".to_string(), "Alice".to_string());
+    let formatted = i18n
+        .format_translation("en", "user.greeting", serde_wasm_bindgen::to_value(&args).unwrap())
+        .unwrap();
+    assert_eq!(formatted, "Hi, Alice!");
+
+    i18n.del_translation("en", "user.bye").unwrap();
+    assert!(!i18n.has_translation("en", "user.bye"));
+
+    // Replacing a nested map with a string must drop the stale leaves.
+    i18n.set_translations("en", serde_wasm_bindgen::to_value(&json!({ "user": "Anonymous" })).unwrap())
+        .unwrap();
+    assert!(!i18n.has_translation("en", "user.greeting"));
+    assert!(i18n.has_translation("en", "user"));
+}
+
+#[wasm_bindgen_test]
+fn test_literal_dotted_keys() {
+    let i18n = I18n::new();
+    i18n.set_key_index_enabled(true);
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({ "a.b": "x", "a": { "b": "y" } })).unwrap(),
+    )
+    .unwrap();
+
+    let literal: String = from_value(i18n.get_translation("en", "a\\.b").unwrap()).unwrap();
+    let nested: String = from_value(i18n.get_translation("en", "a.b").unwrap()).unwrap();
+    assert_eq!(literal, "x");
+    assert_eq!(nested, "y");
+
+    // A failed update leaves the old value reachable through the index.
+    assert!(i18n.update_translation("en", "a.b.c", JsValue::from_str("z")).is_err());
+    assert!(i18n.has_translation("en", "a.b"));
+    assert!(i18n.has_translation("en", "a\\.b"));
+}
+
+#[wasm_bindgen_test]
+fn test_binary_bundle() {
+    let bytes = encode_bundle(
+        r#"{ "en": { "welcome": "Welcome, {username}!", "menu": { "open": "Open" }, "a.b": "x", "empty": {} }, "fr": { "welcome": "Bienvenue, {username}!" } }"#,
+    )
+    .unwrap();
+
+    let i18n = I18n::new();
+    i18n.load_bundle(js_sys::Uint8Array::from(&bytes[..]).into()).unwrap();
+
+    let open: String = from_value(i18n.get_translation("en", "menu.open").unwrap()).unwrap();
+    assert_eq!(open, "Open");
+    let welcome: String = from_value(i18n.get_translation("fr", "welcome").unwrap()).unwrap();
+    assert_eq!(welcome, "Bienvenue, {username}!");
+    let literal: String = from_value(i18n.get_translation("en", "a\\.b").unwrap()).unwrap();
+    assert_eq!(literal, "x");
+    let empty: HashMap<String, String> = from_value(i18n.get_translation("en", "empty").unwrap()).unwrap();
+    assert!(empty.is_empty());
+
+    assert!(i18n.load_bundle(js_sys::Uint8Array::from(&b"nope"[..]).into()).is_err());
+    let error = i18n.load_bundle(JsValue::from_str("WI18")).unwrap_err();
+    assert_eq!(error.as_string().unwrap(), "Expected an ArrayBuffer or Uint8Array");
+
+    // A truncated bundle is rejected before anything is written.
+    let fresh = I18n::new();
+    assert!(fresh.load_bundle(js_sys::Uint8Array::from(&bytes[..bytes.len() - 1]).into()).is_err());
+    assert!(!fresh.has_locale("en"));
+}
+
+#[wasm_bindgen_test]
+fn test_snapshot_roundtrip() {
+    let i18n = I18n::new();
+    i18n.set_key_index_enabled(true);
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({
+            "welcome": "Welcome, {username}!",
+            "menu": { "open": "Open" },
+            "a.b": "x",
+            "empty": {}
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+
+    let from_json = I18n::from_snapshot(JsValue::from_str(&i18n.to_snapshot().unwrap())).unwrap();
+    let bytes = i18n.to_snapshot_binary();
+    let from_binary = I18n::from_snapshot(js_sys::Uint8Array::from(&bytes[..]).into()).unwrap();
+
+    for restored in [from_json, from_binary] {
+        assert!(restored.key_index_enabled());
+        let open: String = from_value(restored.get_translation("en", "menu.open").unwrap()).unwrap();
+        assert_eq!(open, "Open");
+        // Literal dotted keys and empty maps survive both forms.
+        let tree: serde_json::Value = from_value(restored.get_translations("en").unwrap()).unwrap();
+        assert_eq!(tree["a.b"], json!("x"));
+        assert_eq!(tree["empty"], json!({}));
+    }
+
+    assert!(I18n::from_snapshot(JsValue::from_str(r#"{ "version": 99, "translations": {} }"#)).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_gettext_po_roundtrip() {
+    let po = r#"msgid ""
+msgstr ""
+"Language: de\n"
+"Plural-Forms: nplurals=2; plural=(n != 1);\n"
+
+#, fuzzy
+msgid "draft"
+msgstr "Entwurf"
+
+msgctxt "verb"
+msgid "open"
+msgstr "Öffnen"
+
+msgid "file"
+msgid_plural "files"
+msgstr[0] "Datei"
+msgstr[1] "Dateien"
+
+msgid "Loading..."
+msgstr "Laden..."
+
+msgid "File"
+msgstr "Datei"
+
+msgid "File.Open"
+msgstr "Datei öffnen"
+
+msgid "Issue #%d"
+msgstr "Ticket #%d"
+"#;
+
+    let i18n = I18n::new();
+    let report: serde_json::Value = from_value(i18n.import_po("de", po, None).unwrap()).unwrap();
+    assert_eq!(report["imported"], 6);
+    assert_eq!(report["skippedFuzzy"], 1);
+    assert_eq!(report["pluralForms"], "nplurals=2; plural=(n != 1);");
+
+    let open: String = from_value(i18n.get_translation("de", "open#verb").unwrap()).unwrap();
+    assert_eq!(open, "Öffnen");
+    let plural: String = from_value(i18n.get_translation("de", "file.other").unwrap()).unwrap();
+    assert_eq!(plural, "Dateien");
+    assert!(!i18n.has_translation("de", "draft"));
+
+    // msgids are literal keys, never dotted paths.
+    let loading: String = from_value(i18n.get_translation("de", "Loading\\.\\.\\.").unwrap()).unwrap();
+    assert_eq!(loading, "Laden...");
+    let open_file: String = from_value(i18n.get_translation("de", "File\\.Open").unwrap()).unwrap();
+    assert_eq!(open_file, "Datei öffnen");
+    let file: String = from_value(i18n.get_translation("de", "File").unwrap()).unwrap();
+    assert_eq!(file, "Datei");
+
+    let exported = i18n.export_po("de", None).unwrap();
+    assert!(exported.contains("msgctxt \"verb\"\nmsgid \"open\"\nmsgstr \"Öffnen\""));
+    assert!(exported.contains("msgid \"file\"\nmsgid_plural \"files\"\nmsgstr[0] \"Datei\"\nmsgstr[1] \"Dateien\""));
+    assert!(exported.contains("msgid \"Loading...\"\nmsgstr \"Laden...\""));
+    assert!(exported.contains("msgid \"File.Open\"\nmsgstr \"Datei öffnen\""));
+    // A `#` in a msgid without msgctxt is not a context.
+    assert!(exported.contains("\nmsgid \"Issue #%d\"\nmsgstr \"Ticket #%d\""));
+    assert!(!exported.contains("msgctxt \"%d\""));
+
+    // POSIX locale names reach the same plural rules as BCP 47 tags.
+    let po = r#"msgid ""
+msgstr ""
+"Plural-Forms: nplurals=2; plural=(n > 1);\n"
+
+msgid "file"
+msgid_plural "files"
+msgstr[0] "arquivo"
+msgstr[1] "arquivos"
+"#;
+    i18n.import_po("pt_BR", po, None).unwrap();
+    let one: String = from_value(i18n.get_translation("pt_BR", "file.one").unwrap()).unwrap();
+    assert_eq!(one, "arquivo");
+    let exported = i18n.export_po("pt_BR", Some("nplurals=2; plural=(n > 1);".to_string())).unwrap();
+    assert!(exported.contains("msgstr[0] \"arquivo\"\nmsgstr[1] \"arquivos\""));
+    i18n.set_translations("pt_PT", serde_wasm_bindgen::to_value(&json!({ "hello": "Olá" })).unwrap()).unwrap();
+    assert!(i18n.export_po("pt_PT", None).unwrap().contains("msgid \"hello\"\nmsgstr \"Olá\""));
+}
+
+#[wasm_bindgen_test]
+fn test_fluent_resource() {
+    let ftl = r#"
+-brand = Firefox
+emails = { $count ->
+    [one] You have one email in { -brand }.
+   *[other] You have { $count } emails in { -brand }.
+}
+login =
+    .placeholder = Email address
+"#;
+
+    let i18n = I18n::new();
+    i18n.import_ftl("en", ftl).unwrap();
+
+    let one = i18n
+        .format_translation("en", "emails", serde_wasm_bindgen::to_value(&json!({ "count": 1 })).unwrap())
+        .unwrap();
+    assert_eq!(one, "You have one email in Firefox.");
+    let many = i18n
+        .format_translation("en", "emails", serde_wasm_bindgen::to_value(&json!({ "count": 5 })).unwrap())
+        .unwrap();
+    assert_eq!(many, "You have 5 emails in Firefox.");
+
+    let placeholder: String = from_value(i18n.get_translation("en", "login.placeholder").unwrap()).unwrap();
+    assert_eq!(placeholder, "Email address");
+    assert!(i18n.import_ftl("en", "not an entry").is_err());
+
+    // Only the imported keys are Fluent; JSON strings keep `{name}` placeholders.
+    i18n.set_translations("en", serde_wasm_bindgen::to_value(&json!({ "hello": "Hello, {name}!" })).unwrap())
+        .unwrap();
+    let hello = i18n
+        .format_translation("en", "hello", serde_wasm_bindgen::to_value(&json!({ "name": "Ann" })).unwrap())
+        .unwrap();
+    assert_eq!(hello, "Hello, Ann!");
+
+    let restored = I18n::from_snapshot(JsValue::from_str(&i18n.to_snapshot().unwrap())).unwrap();
+    let many = restored
+        .format_translation("en", "emails", serde_wasm_bindgen::to_value(&json!({ "count": 5 })).unwrap())
+        .unwrap();
+    assert_eq!(many, "You have 5 emails in Firefox.");
+}
+
+#[wasm_bindgen_test]
+fn test_xliff_roundtrip() {
+    let xliff = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="2.0" xmlns="urn:oasis:names:tc:xliff:document:2.0" srcLang="en" trgLang="de">
+  <file id="app">
+    <unit id="menu.open">
+      <notes><note>Verb, used on buttons</note></notes>
+      <segment state="translated">
+        <source>Open</source>
+        <target>Öffnen</target>
+      </segment>
+    </unit>
+    <unit id="menu.close">
+      <segment state="initial">
+        <source>Close</source>
+      </segment>
+    </unit>
+  </file>
+</xliff>"#;
+
+    let i18n = I18n::new();
+    let report: serde_json::Value = from_value(i18n.import_xliff(xliff, None, Some(true)).unwrap()).unwrap();
+    assert_eq!(report["imported"], 1);
+    assert_eq!(report["skipped"], 1);
+    assert_eq!(report["targetLocale"], "de");
+    assert_eq!(report["notes"]["menu.open"][0], "Verb, used on buttons");
+
+    let open: String = from_value(i18n.get_translation("de", "menu.open").unwrap()).unwrap();
+    assert_eq!(open, "Öffnen");
+    assert!(i18n.has_translation("en", "menu.close"));
+
+    let exported = i18n.export_xliff("en", "de", Some("1.2".to_string())).unwrap();
+    assert!(exported.contains("<trans-unit id=\"menu.open\">"));
+    assert!(exported.contains("<target state=\"needs-translation\"></target>"));
+
+    // Source notes still show for keys the target has no metadata for.
+    i18n.set_metadata("en", "menu.close", serde_wasm_bindgen::to_value(&json!({ "description": "Closes the menu" })).unwrap())
+        .unwrap();
+    let exported = i18n.export_xliff("en", "de", Some("1.2".to_string())).unwrap();
+    assert!(exported.contains("<note>Verb, used on buttons</note>"));
+    assert!(exported.contains("<note>Closes the menu</note>"));
+}
+
+#[wasm_bindgen_test]
+fn test_xliff_import_is_atomic() {
+    let unit = |id: &str, target: &str| {
+        format!("<unit {}><segment><source>x</source><target>{}</target></segment></unit>", id, target)
+    };
+    let document = |units: String| {
+        format!("<xliff version=\"2.0\" srcLang=\"en\" trgLang=\"de\"><file id=\"app\">{}</file></xliff>", units)
+    };
+
+    let i18n = I18n::new();
+    let report: serde_json::Value =
+        from_value(i18n.import_xliff(&document(unit("", "Ohne") + &unit("id=\"menu\"", "Menü")), None, None).unwrap()).unwrap();
+    assert_eq!(report["imported"], 1);
+    assert_eq!(report["skipped"], 1);
+    assert!(!i18n.has_translation("de", ""));
+
+    // `menu` is a string, so `menu.open` fails and `title` must not be written either.
+    let clash = document(unit("id=\"title\"", "Titel") + &unit("id=\"menu.open\"", "Öffnen"));
+    assert!(i18n.import_xliff(&clash, None, None).is_err());
+    assert!(!i18n.has_translation("de", "title"));
+    let menu: String = from_value(i18n.get_translation("de", "menu").unwrap()).unwrap();
+    assert_eq!(menu, "Menü");
+}
+
+#[wasm_bindgen_test]
+fn test_parse_yaml_toml_properties() {
+    let i18n = I18n::new();
+
+    let rails: HashMap<String, serde_json::Value> =
+        from_value(parse_yaml_translations("en:\n  welcome: Welcome, {username}!\n  items: 5\n").unwrap()).unwrap();
+    i18n.set_translations("en", serde_wasm_bindgen::to_value(&rails["en"]).unwrap()).unwrap();
+    let items: String = from_value(i18n.get_translation("en", "items").unwrap()).unwrap();
+    assert_eq!(items, "5");
+
+    i18n.set_translations("fr", parse_toml_translations("[menu]\nopen = \"Ouvrir\"\n").unwrap()).unwrap();
+    let open: String = from_value(i18n.get_translation("fr", "menu.open").unwrap()).unwrap();
+    assert_eq!(open, "Ouvrir");
+
+    i18n.set_translations("de", parse_properties_translations("menu.open = \\u00D6ffnen\n").unwrap()).unwrap();
+    let open: String = from_value(i18n.get_translation("de", "menu.open").unwrap()).unwrap();
+    assert_eq!(open, "Öffnen");
+
+    assert!(parse_properties_translations("a=1\na.b=2").is_err());
+    let error = parse_properties_translations("a.b=2\n\na=1").unwrap_err().as_string().unwrap();
+    assert!(error.contains("line 3"), "{}", error);
+}
+
+#[wasm_bindgen_test]
+fn test_android_and_apple_strings() {
+    let i18n = I18n::new();
+    i18n.import_android_strings("en", r#"<?xml version="1.0" encoding="utf-8"?>
+<resources>
+    <string name="welcome">Hello, %1$s! It\'s %2$d°</string>
+    <plurals name="emails">
+        <item quantity="one">%d email</item>
+        <item quantity="other">%d emails</item>
+    </plurals>
+    <string-array name="days">
+        <item>Mon</item>
+        <item>Tue</item>
+    </string-array>
+    <string name="unread">You have <b><xliff:g id="count" example="3">%1$d</xliff:g> new</b> messages</string>
+</resources>"#).unwrap();
+
+    let welcome: String = from_value(i18n.get_translation("en", "welcome").unwrap()).unwrap();
+    assert_eq!(welcome, "Hello, {1}! It's {2}°");
+    let emails: HashMap<String, String> = from_value(i18n.get_translation("en", "emails").unwrap()).unwrap();
+    assert_eq!(emails["other"], "{1} emails");
+    let tuesday: String = from_value(i18n.get_translation("en", "days.1").unwrap()).unwrap();
+    assert_eq!(tuesday, "Tue");
+
+    let xml = i18n.export_android_strings("en").unwrap();
+    assert!(xml.contains(r#"<string name="welcome">Hello, %1$s! It\'s %2$s°</string>"#));
+    assert!(xml.contains(r#"<item quantity="one">%1$s email</item>"#));
+    assert!(xml.contains("<string-array name=\"days\">"));
+    let unread: String = from_value(i18n.get_translation("en", "unread").unwrap()).unwrap();
+    assert_eq!(unread, "You have <b>{1} new</b> messages");
+    assert!(xml.contains(r#"<string name="unread">You have <b>%1$s new</b> messages</string>"#));
+
+    i18n.import_apple_strings("fr", "/* Greeting */\n\"welcome\" = \"Bonjour, %@ !\";").unwrap();
+    let welcome: String = from_value(i18n.get_translation("fr", "welcome").unwrap()).unwrap();
+    assert_eq!(welcome, "Bonjour, {1} !");
+    assert_eq!(i18n.export_apple_strings("fr").unwrap(), "\"welcome\" = \"Bonjour, %1$@ !\";\n");
+    i18n.import_apple_strings("fr", "\"Loading...\" = \"Chargement…\";").unwrap();
+    assert!(i18n.has_translation("fr", "Loading\\.\\.\\."));
+    assert!(i18n.export_apple_strings("fr").unwrap().contains("\"Loading...\" = \"Chargement…\";"));
+
+    let stringsdict = i18n.export_apple_stringsdict("en").unwrap();
+    assert!(stringsdict.contains("<string>%1$d emails</string>"));
+    i18n.import_apple_stringsdict("de", &stringsdict).unwrap();
+    let emails: HashMap<String, String> = from_value(i18n.get_translation("de", "emails").unwrap()).unwrap();
+    assert_eq!(emails["one"], "{1} email");
+}
+
+#[wasm_bindgen_test]
+fn test_mobile_roundtrip_keeps_nested_keys() {
+    let i18n = I18n::new();
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({
+            "menu": { "open": "Open" },
+            "days": { "0": "Mon", "1": "Tue" },
+            "File.Open": "Open file",
+            "Loading...": "Loading..."
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+
+    let strings = i18n.export_apple_strings("en").unwrap();
+    assert!(strings.contains("\"menu.open\" = \"Open\";"));
+    assert!(strings.contains("\"File\\\\.Open\" = \"Open file\";"));
+    i18n.import_apple_strings("fr", &strings).unwrap();
+    i18n.import_android_strings("de", &i18n.export_android_strings("en").unwrap()).unwrap();
+
+    let en: serde_json::Value = from_value(i18n.get_translations("en").unwrap()).unwrap();
+    for locale in ["fr", "de"] {
+        let tree: serde_json::Value = from_value(i18n.get_translations(locale).unwrap()).unwrap();
+        assert_eq!(tree, en);
+    }
+}
+
+#[wasm_bindgen_test]
+fn test_arb_roundtrip() {
+    let i18n = I18n::new();
+    let locale = i18n.import_arb(r#"{
+  "@@locale": "en",
+  "greeting": "Hello {name}",
+  "@greeting": {
+    "description": "Home screen greeting",
+    "placeholders": { "name": { "type": "String", "example": "Bob" } }
+  }
+}"#, None).unwrap();
+    assert_eq!(locale, "en");
+
+    let greeting: String = from_value(i18n.get_translation("en", "greeting").unwrap()).unwrap();
+    assert_eq!(greeting, "Hello {name}");
+    assert!(!i18n.has_translation("en", "@greeting"));
+
+    let exported: serde_json::Value = serde_json::from_str(&i18n.export_arb("en").unwrap()).unwrap();
+    assert_eq!(exported["@@locale"], "en");
+    assert_eq!(exported["greeting"], "Hello {name}");
+    assert_eq!(exported["@greeting"]["description"], "Home screen greeting");
+    assert_eq!(exported["@greeting"]["placeholders"]["name"]["example"], "Bob");
+
+    assert!(i18n.import_arb(r#"{ "greeting": "Hi" }"#, None).is_err());
+    assert_eq!(i18n.import_arb(r#"{ "greeting": "Hallo {name}" }"#, Some("de".to_string())).unwrap(), "de");
+}
+
+#[wasm_bindgen_test]
+fn test_translation_metadata() {
+    let i18n = I18n::new();
+    i18n.set_translations("en", serde_wasm_bindgen::to_value(&json!({ "greeting": "Hello {name}" })).unwrap()).unwrap();
+    assert!(i18n.get_metadata("en", "greeting").unwrap().is_undefined());
+
+    i18n.set_metadata(
+        "en",
+        "greeting",
+        serde_wasm_bindgen::to_value(&json!({
+            "description": "Home screen greeting",
+            "maxLength": 40,
+            "placeholders": { "name": { "type": "String", "example": "Bob" } },
+            "lastModified": "2024-05-01T12:00:00Z"
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+
+    let greeting: String = from_value(i18n.get_translation("en", "greeting").unwrap()).unwrap();
+    assert_eq!(greeting, "Hello {name}");
+    let metadata: serde_json::Value = from_value(i18n.get_metadata("en", "greeting").unwrap()).unwrap();
+    assert_eq!(metadata["maxLength"], 40);
+    assert_eq!(metadata["placeholders"]["name"]["example"], "Bob");
+
+    let restored = I18n::from_snapshot(JsValue::from_str(&i18n.to_snapshot().unwrap())).unwrap();
+    let metadata: serde_json::Value = from_value(restored.get_metadata("en", "greeting").unwrap()).unwrap();
+    assert_eq!(metadata["description"], "Home screen greeting");
+
+    let po = i18n.export_po("en", None).unwrap();
+    assert!(po.contains("#. Home screen greeting\nmsgid \"greeting\""));
+    let xliff = i18n.export_xliff("en", "de", None).unwrap();
+    assert!(xliff.contains("maxwidth=\"40\""));
+
+    let de = I18n::new();
+    de.import_xliff(&xliff.replace("<target state=\"needs-translation\"></target>", "<target>Hallo {name}</target>"), None, None)
+        .unwrap();
+    let metadata: serde_json::Value = from_value(de.get_metadata("de", "greeting").unwrap()).unwrap();
+    assert_eq!(metadata["description"], "Home screen greeting");
+    assert_eq!(metadata["maxLength"], 40);
+
+    i18n.del_translation("en", "greeting").unwrap();
+    assert!(i18n.get_metadata("en", "greeting").unwrap().is_undefined());
+}
+
+#[wasm_bindgen_test]
+fn test_context_lookup() {
+    let i18n = I18n::new();
+    i18n.set_translations(
+        "de",
+        serde_wasm_bindgen::to_value(&json!({
+            "menu": { "open": "Öffnen", "open#status": "Offen ({since})" }
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+
+    let status = serde_wasm_bindgen::to_value(&json!({ "context": "status" })).unwrap();
+    let verb = serde_wasm_bindgen::to_value(&json!({ "context": "verb" })).unwrap();
+
+    let open: String = from_value(i18n.get_translation_with_options("de", "menu.open", status.clone()).unwrap()).unwrap();
+    assert_eq!(open, "Offen ({since})");
+    let open: String = from_value(i18n.get_translation_with_options("de", "menu.open", verb).unwrap()).unwrap();
+    assert_eq!(open, "Öffnen");
+    let open: String = from_value(i18n.get_translation("de", "menu.open").unwrap()).unwrap();
+    assert_eq!(open, "Öffnen");
+
+    let args = serde_wasm_bindgen::to_value(&json!({ "since": "9:00" })).unwrap();
+    assert_eq!(i18n.format_translation_with_options("de", "menu.open", args, status).unwrap(), "Offen (9:00)");
+}
+
+#[wasm_bindgen_test]
+fn test_validate_against_source() {
+    let i18n = I18n::new();
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({
+            "welcome": "Welcome, {username}!",
+            "menu": { "open": "Open", "close": "Close" },
+            "emails": { "one": "One email", "other": "{count} emails" }
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+    i18n.set_translations(
+        "pl",
+        serde_wasm_bindgen::to_value(&json!({
+            "welcome": "Witaj!",
+            "menu": { "open": "Otwórz", "legacy": "Stare" },
+            "emails": { "one": "Jeden e-mail", "few": "{count} e-maile", "many": "{count} e-maili", "other": "{count} e-maila" }
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+    i18n.set_translations(
+        "de",
+        serde_wasm_bindgen::to_value(&json!({
+            "welcome": "Willkommen, {username}!",
+            "menu": "Menü",
+            "emails": { "one": "Eine E-Mail", "other": "{count, plural, other {# E-Mails}" }
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+
+    let report: serde_json::Value = from_value(i18n.validate("en").unwrap()).unwrap();
+    assert_eq!(report["valid"], false);
+
+    let pl = &report["locales"]["pl"];
+    assert_eq!(pl["missing"], json!(["menu.close"]));
+    assert_eq!(pl["orphans"], json!(["menu.legacy"]));
+    assert_eq!(pl["placeholderMismatches"], json!([{ "key": "welcome", "missing": ["username"], "extra": [] }]));
+
+    let de = &report["locales"]["de"];
+    assert_eq!(de["shapeConflicts"], json!([{ "key": "menu", "source": "nested", "target": "string" }]));
+    assert_eq!(de["syntaxErrors"][0]["key"], "emails.other");
+
+    assert!(i18n.validate("fr").is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_placeholders_and_strict_format() {
+    let i18n = I18n::new();
+    i18n.set_key_index_enabled(true);
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({
+            "welcome": "Welcome, {username}!",
+            "cart": "{user} has {count, plural, one {# item} other {# items}} since {since, date, short}"
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+
+    let placeholders: serde_json::Value = from_value(i18n.get_placeholders("en", "cart", JsValue::UNDEFINED).unwrap()).unwrap();
+    assert_eq!(
+        placeholders,
+        json!([
+            { "name": "user", "type": "plain" },
+            { "name": "count", "type": "plural" },
+            { "name": "since", "type": "date" }
+        ])
+    );
+
+    let empty = serde_wasm_bindgen::to_value(&json!({})).unwrap();
+    let strict = serde_wasm_bindgen::to_value(&json!({ "strict": true })).unwrap();
+    assert_eq!(
+        i18n.format_translation("en", "welcome", empty.clone()).unwrap(),
+        "Welcome, {username}!"
+    );
+    let error = i18n.format_translation_with_options("en", "welcome", empty, strict.clone()).unwrap_err();
+    assert_eq!(error.as_string().unwrap(), "Missing argument 'username' for 'welcome'");
+
+    let args = serde_wasm_bindgen::to_value(&json!({ "username": "Alice" })).unwrap();
+    assert_eq!(i18n.format_translation_with_options("en", "welcome", args, strict.clone()).unwrap(), "Welcome, Alice!");
+
+    // Without `icu` only `{name}` substitution applies, so strict mode checks
+    // exactly those placeholders and leaves other braces alone.
+    i18n.set_translations("en", serde_wasm_bindgen::to_value(&json!({ "braces": "Use { and } in {lang}" })).unwrap())
+        .unwrap();
+    let lang = || serde_wasm_bindgen::to_value(&json!({ "lang": "CSS" })).unwrap();
+    assert_eq!(i18n.format_translation_with_options("en", "braces", lang(), strict.clone()).unwrap(), "Use { and } in CSS");
+    let user = || serde_wasm_bindgen::to_value(&json!({ "user": "Ann" })).unwrap();
+    let error = i18n
+        .format_translation_with_options("en", "cart", user(), serde_wasm_bindgen::to_value(&json!({ "strict": true, "icu": true })).unwrap())
+        .unwrap_err();
+    assert_eq!(error.as_string().unwrap(), "Missing argument 'count' for 'cart'");
+    assert_eq!(
+        i18n.format_translation_with_options("en", "cart", user(), strict).unwrap(),
+        "Ann has {count, plural, one {# item} other {# items}} since {since, date, short}"
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_format_modes() {
+    let i18n = I18n::new();
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({ "welcome": "Welcome, {username}!" })).unwrap(),
+    )
+    .unwrap();
+    let options = |value: serde_json::Value| serde_wasm_bindgen::to_value(&value).unwrap();
+    let empty = || options(json!({}));
+    let extra = || options(json!({ "username": "Ann", "x": 1 }));
+
+    assert_eq!(i18n.format_mode(), "lenient");
+    assert_eq!(i18n.format_translation("en", "welcome", extra()).unwrap(), "Welcome, Ann!");
+
+    let error = i18n.format_translation_with_options("en", "welcome", extra(), options(json!({ "mode": "strict" }))).unwrap_err();
+    assert_eq!(error.as_string().unwrap(), "Unused argument 'x' for 'welcome'");
+
+    let replace = options(json!({ "mode": "replace-missing" }));
+    assert_eq!(i18n.format_translation_with_options("en", "welcome", empty(), replace).unwrap(), "Welcome, ⟦username⟧!");
+
+    i18n.set_format_mode("replace-missing", Some("[{name}]".to_string())).unwrap();
+    assert_eq!(i18n.format_mode(), "replace-missing");
+    assert_eq!(i18n.format_translation("en", "welcome", empty()).unwrap(), "Welcome, [username]!");
+    let lenient = options(json!({ "mode": "lenient" }));
+    assert_eq!(i18n.format_translation_with_options("en", "welcome", empty(), lenient).unwrap(), "Welcome, {username}!");
+
+    assert!(i18n.set_format_mode("loud", None).is_err());
+}
+
+#[wasm_bindgen_test]
+fn test_escaped_format_and_tokens() {
+    let i18n = I18n::new();
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({ "welcome": "Hi <b>{name}</b>! <script>" })).unwrap(),
+    )
+    .unwrap();
+    let args = || serde_wasm_bindgen::to_value(&json!({ "name": "<i>Ann</i>" })).unwrap();
+    let escape = serde_wasm_bindgen::to_value(&json!({ "escape": true })).unwrap();
+
+    assert_eq!(
+        i18n.format_translation_with_options("en", "welcome", args(), escape).unwrap(),
+        "Hi <b>&lt;i&gt;Ann&lt;/i&gt;</b>! &lt;script&gt;"
+    );
+    assert_eq!(
+        i18n.format_translation("en", "welcome", args()).unwrap(),
+        "Hi <b><i>Ann</i></b>! <script>"
+    );
+
+    let tokens: serde_json::Value = from_value(i18n.format_to_tokens("en", "welcome", args(), JsValue::UNDEFINED).unwrap()).unwrap();
+    assert_eq!(
+        tokens,
+        json!([
+            { "type": "text", "value": "Hi " },
+            { "type": "tag", "name": "b", "children": [{ "type": "text", "value": "<i>Ann</i>" }] },
+            { "type": "text", "value": "! <script>" }
+        ])
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_format_rich() {
+    let i18n = I18n::new();
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({ "terms": "Read the <link>terms <b>now</b></link>, {name}<i>!</i>" })).unwrap(),
+    )
+    .unwrap();
+    let tags = js_sys::Object::new();
+    let wrap = |name: &str| js_sys::Function::new_with_args("children", &format!("return '[{}:' + children.join('|') + ']'", name));
+    js_sys::Reflect::set(&tags, &"link".into(), &wrap("link")).unwrap();
+    js_sys::Reflect::set(&tags, &"b".into(), &wrap("b")).unwrap();
+
+    let args = serde_wasm_bindgen::to_value(&json!({ "name": "<b>Ann</b>" })).unwrap();
+    let parts = i18n.format_rich("en", "terms", args, tags.into(), JsValue::UNDEFINED).unwrap();
+    let parts: Vec<String> = parts.iter().map(|part| part.as_string().unwrap()).collect();
+    assert_eq!(parts, vec!["Read the ", "[link:terms |[b:now]]", ", <b>Ann</b><i>!</i>"]);
+}
+
+#[wasm_bindgen_test]
+fn test_format_to_parts() {
+    let i18n = I18n::new();
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({
+            "inbox": "{name}, you have {count, plural, =0 {no messages} one {# new message} other {# new messages}}",
+            "total": "Total: {amount, number, currency} on {day, date, short}",
+            "place": "{n, selectordinal, one {#st} two {#nd} few {#rd} other {#th}}",
+            "delete": "Supprimer l'{item}"
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+
+    let args = || serde_wasm_bindgen::to_value(&json!({ "name": "Ann", "count": 5 })).unwrap();
+    let icu = || serde_wasm_bindgen::to_value(&json!({ "icu": true })).unwrap();
+    let parts: serde_json::Value = from_value(i18n.format_to_parts("en", "inbox", args(), JsValue::UNDEFINED).unwrap()).unwrap();
+    assert_eq!(
+        parts,
+        json!([
+            { "type": "argument", "value": "Ann", "name": "name" },
+            { "type": "literal", "value": ", you have " },
+            { "type": "plural", "value": "5", "name": "count" },
+            { "type": "literal", "value": " new messages" }
+        ])
+    );
+    assert_eq!(
+        i18n.format_translation_with_options("en", "inbox", args(), icu()).unwrap(),
+        "Ann, you have 5 new messages"
+    );
+    let none = serde_wasm_bindgen::to_value(&json!({ "name": "Ann", "count": 0 })).unwrap();
+    assert_eq!(i18n.format_translation_with_options("en", "inbox", none, icu()).unwrap(), "Ann, you have no messages");
+
+    let name_only = serde_wasm_bindgen::to_value(&json!({ "name": "Ann" })).unwrap();
+    let replace = serde_wasm_bindgen::to_value(&json!({ "mode": "replace-missing" })).unwrap();
+    let parts: serde_json::Value = from_value(i18n.format_to_parts("en", "inbox", name_only, replace).unwrap()).unwrap();
+    assert_eq!(parts[2], json!({ "type": "plural", "value": "⟦count⟧", "name": "count" }));
+
+    let args = serde_wasm_bindgen::to_value(&json!({ "amount": 9.5, "day": "2024-05-01" })).unwrap();
+    let parts: serde_json::Value = from_value(i18n.format_to_parts("en", "total", args, JsValue::UNDEFINED).unwrap()).unwrap();
+    assert_eq!(parts[1], json!({ "type": "number", "value": "9.5", "name": "amount", "style": "currency" }));
+    assert_eq!(parts[3], json!({ "type": "date", "value": "2024-05-01", "name": "day", "style": "short" }));
+
+    let third = serde_wasm_bindgen::to_value(&json!({ "n": 23 })).unwrap();
+    assert_eq!(i18n.format_translation_with_options("en", "place", third, icu()).unwrap(), "23rd");
+
+    // Without `icu`, an apostrophe is plain text rather than ICU quoting.
+    let item = serde_wasm_bindgen::to_value(&json!({ "item": "élément" })).unwrap();
+    assert_eq!(i18n.format_translation("en", "delete", item).unwrap(), "Supprimer l'élément");
+}
+
+#[wasm_bindgen_test]
+fn test_change_events() {
+    let i18n = I18n::new();
+    let events = js_sys::Array::new();
+    let recorder = js_sys::Function::new_with_args("events", "return (event) => { events.push(event); }");
+    let record: js_sys::Function = recorder.call1(&JsValue::NULL, &events).unwrap().unchecked_into();
+    let subscription = i18n.on("change", record.clone()).unwrap();
+    i18n.on("localeChanged", record).unwrap();
+    assert!(i18n.on("changed", js_sys::Function::new_no_args("")).is_err());
+
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({ "menu": { "open": "Open", "close": "Close" } })).unwrap(),
+    )
+    .unwrap();
+    i18n.update_translation("en", "menu.open", JsValue::from("Open…")).unwrap();
+    i18n.del_translation("en", "menu.close").unwrap();
+    i18n.set_locale("en");
+    i18n.set_locale("en");
+    assert_eq!(i18n.locale(), Some("en".to_string()));
+
+    subscription.unsubscribe();
+    i18n.update_translation("en", "menu.open", JsValue::from("Open")).unwrap();
+
+    let events: serde_json::Value = from_value(events.into()).unwrap();
+    assert_eq!(
+        events,
+        json!([
+            { "locale": "en", "keys": ["menu.close", "menu.open"] },
+            { "locale": "en", "keys": ["menu.open"] },
+            { "locale": "en", "keys": ["menu.close"] },
+            { "locale": "en", "previous": null }
+        ])
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_failed_mutation_does_not_leak_changes() {
+    let i18n = I18n::new();
+    i18n.set_translations("en", serde_wasm_bindgen::to_value(&json!({ "menu": "Menu" })).unwrap()).unwrap();
+    let events = js_sys::Array::new();
+    let recorder = js_sys::Function::new_with_args("events", "return (event) => { events.push(event); }");
+    i18n.on("change", recorder.call1(&JsValue::NULL, &events).unwrap().unchecked_into()).unwrap();
+
+    // `menu.open` fails on the string at `menu`, so `a` is not written either.
+    assert!(i18n.import_arb(r#"{ "a": "A", "menu.open": "Open" }"#, Some("en".to_string())).is_err());
+    assert!(!i18n.has_translation("en", "a"));
+    i18n.update_translation("en", "menu", JsValue::from("Menu…")).unwrap();
+
+    let events: serde_json::Value = from_value(events.into()).unwrap();
+    assert_eq!(events, json!([{ "locale": "en", "keys": ["menu"] }]));
+}
+
+#[wasm_bindgen_test]
+fn test_batch_is_atomic() {
+    let i18n = I18n::new();
+    i18n.set_key_index_enabled(true);
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({ "menu": { "open": "Open", "legacy": "Old" }, "title": "App" })).unwrap(),
+    )
+    .unwrap();
+    let events = js_sys::Array::new();
+    let recorder = js_sys::Function::new_with_args("events", "return (event) => { events.push(event); }");
+    i18n.on("change", recorder.call1(&JsValue::NULL, &events).unwrap().unchecked_into()).unwrap();
+
+    let failing = serde_wasm_bindgen::to_value(&json!([
+        { "op": "set", "locale": "en", "key": "menu.close", "value": "Close" },
+        { "op": "update", "locale": "en", "key": "title.short", "value": "A" }
+    ]))
+    .unwrap();
+    let error = i18n.batch(failing).unwrap_err().as_string().unwrap();
+    assert!(error.starts_with("Operation 1 (update 'title.short')"), "{}", error);
+    assert!(!i18n.has_translation("en", "menu.close"));
+    assert_eq!(events.length(), 0);
+
+    let ops = serde_wasm_bindgen::to_value(&json!([
+        { "op": "set", "locale": "en", "key": "menu.close", "value": "Close" },
+        { "op": "set", "locale": "de", "key": "menu.open", "value": "Öffnen" },
+        { "op": "delete", "locale": "en", "key": "menu.legacy" }
+    ]))
+    .unwrap();
+    i18n.batch(ops).unwrap();
+    let close: String = from_value(i18n.get_translation("en", "menu.close").unwrap()).unwrap();
+    assert_eq!(close, "Close");
+    assert!(!i18n.has_translation("en", "menu.legacy"));
+    assert!(i18n.has_translation("de", "menu.open"));
+
+    let events: serde_json::Value = from_value(events.into()).unwrap();
+    assert_eq!(
+        events,
+        json!([
+            { "locale": "de", "keys": ["menu.open"] },
+            { "locale": "en", "keys": ["menu.close", "menu.legacy"] }
+        ])
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_undo_redo_history() {
+    let i18n = I18n::new();
+    i18n.set_history_enabled(true, Some(10));
+    i18n.set_translations("en", serde_wasm_bindgen::to_value(&json!({ "hello": "Hello", "bye": "Bye" })).unwrap())
+        .unwrap();
+    i18n.update_translation("en", "hello", JsValue::from("Hi")).unwrap();
+    i18n.del_translation("en", "bye").unwrap();
+    let hello = |i18n: &I18n| -> String { from_value(i18n.get_translation("en", "hello").unwrap()).unwrap() };
+
+    let patch: serde_json::Value = from_value(i18n.export_history().unwrap()).unwrap();
+    assert_eq!(
+        patch,
+        json!([
+            { "op": "set", "locale": "en", "key": "hello", "value": "Hi" },
+            { "op": "delete", "locale": "en", "key": "bye" }
+        ])
+    );
+
+    assert!(i18n.undo().unwrap());
+    assert!(i18n.has_translation("en", "bye"));
+    assert!(i18n.undo().unwrap());
+    assert_eq!(hello(&i18n), "Hello");
+    assert!(i18n.can_redo());
+    assert!(i18n.redo().unwrap());
+    assert_eq!(hello(&i18n), "Hi");
+
+    i18n.update_translation("en", "hello", JsValue::from("Hey")).unwrap();
+    assert!(!i18n.can_redo());
+    assert!(!i18n.redo().unwrap());
+
+    let other = I18n::new();
+    other.set_translations("en", serde_wasm_bindgen::to_value(&json!({ "hello": "Hello" })).unwrap()).unwrap();
+    other.batch(i18n.export_history().unwrap()).unwrap();
+    assert_eq!(hello(&other), "Hey");
+}
+
+#[wasm_bindgen_test]
+fn test_undo_restores_metadata() {
+    let i18n = I18n::new();
+    i18n.set_history_enabled(true, None);
+    i18n.set_translations("en", serde_wasm_bindgen::to_value(&json!({ "menu": { "open": "Open" } })).unwrap())
+        .unwrap();
+    i18n.set_metadata("en", "menu.open", serde_wasm_bindgen::to_value(&json!({ "description": "Verb" })).unwrap()).unwrap();
+
+    i18n.del_translation("en", "menu").unwrap();
+    assert!(i18n.get_metadata("en", "menu.open").unwrap().is_undefined());
+
+    assert!(i18n.undo().unwrap());
+    let metadata: serde_json::Value = from_value(i18n.get_metadata("en", "menu.open").unwrap()).unwrap();
+    assert_eq!(metadata["description"], "Verb");
+    assert!(i18n.redo().unwrap());
+    assert!(i18n.get_metadata("en", "menu.open").unwrap().is_undefined());
+}
+
+#[wasm_bindgen_test]
+async fn test_load_translations_expands_dotted_keys() {
+    let i18n = I18n::new();
+    let url = "data:application/json,%7B%22en%22%3A%7B%22menu.open%22%3A%22Open%22%7D%7D";
+    i18n.load_translations(url).await.unwrap();
+    let open: String = from_value(i18n.get_translation("en", "menu.open").unwrap()).unwrap();
+    assert_eq!(open, "Open");
+    assert!(!i18n.has_translation("en", "menu\\.open"));
+}
+
+#[wasm_bindgen_test]
+fn test_metadata_edits_are_undoable() {
+    let i18n = I18n::new();
+    i18n.set_history_enabled(true, None);
+    i18n.set_translations("en", serde_wasm_bindgen::to_value(&json!({ "greeting": "Hello" })).unwrap()).unwrap();
+    i18n.set_metadata("en", "greeting", serde_wasm_bindgen::to_value(&json!({ "description": "Home" })).unwrap()).unwrap();
+
+    assert!(i18n.undo().unwrap());
+    assert!(i18n.get_metadata("en", "greeting").unwrap().is_undefined());
+    assert!(i18n.has_translation("en", "greeting"));
+    assert!(i18n.redo().unwrap());
+    let metadata: serde_json::Value = from_value(i18n.get_metadata("en", "greeting").unwrap()).unwrap();
+    assert_eq!(metadata["description"], "Home");
+
+    // `C:\temp` and `C:\\temp` spell the same key.
+    i18n.set_metadata("en", "C:\\temp", serde_wasm_bindgen::to_value(&json!({ "description": "Path" })).unwrap()).unwrap();
+    let metadata: serde_json::Value = from_value(i18n.get_metadata("en", "C:\\\\temp").unwrap()).unwrap();
+    assert_eq!(metadata["description"], "Path");
+}
+
+#[wasm_bindgen_test]
+fn test_undo_restores_deleted_locale() {
+    let i18n = I18n::new();
+    i18n.set_history_enabled(true, None);
+    i18n.import_ftl("en", "hello = Hello, { $name }!").unwrap();
+    i18n.set_metadata("en", "hello", serde_wasm_bindgen::to_value(&json!({ "description": "Greeting" })).unwrap()).unwrap();
+
+    i18n.del_translations("en").unwrap();
+    assert!(!i18n.has_locale("en"));
+
+    assert!(i18n.undo().unwrap());
+    assert!(i18n.has_locale("en"));
+    let hello = i18n
+        .format_translation("en", "hello", serde_wasm_bindgen::to_value(&json!({ "name": "Ann" })).unwrap())
+        .unwrap();
+    assert_eq!(hello, "Hello, Ann!");
+    let metadata: serde_json::Value = from_value(i18n.get_metadata("en", "hello").unwrap()).unwrap();
+    assert_eq!(metadata["description"], "Greeting");
+
+    assert!(i18n.redo().unwrap());
+    assert!(!i18n.has_locale("en"));
+}
+
+#[wasm_bindgen_test]
+fn test_diff_and_apply_patch() {
+    let i18n = I18n::new();
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({ "menu": { "open": "Open", "legacy": "Old" }, "title": "Title" })).unwrap(),
+    )
+    .unwrap();
+    i18n.set_translations("de", serde_wasm_bindgen::to_value(&json!({ "menu": { "open": "Öffnen" }, "title": "Title" })).unwrap())
+        .unwrap();
+
+    let patch: serde_json::Value = from_value(i18n.diff("en", JsValue::from("de")).unwrap()).unwrap();
+    assert_eq!(
+        patch,
+        json!([
+            { "op": "remove", "path": "menu.legacy" },
+            { "op": "replace", "path": "menu.open", "value": "Öffnen", "oldValue": "Open" }
+        ])
+    );
+
+    let incoming = serde_wasm_bindgen::to_value(&json!({ "menu": { "open": "Open", "close": "Close" }, "title": "Title" })).unwrap();
+    let patch = i18n.diff("en", incoming).unwrap();
+    let ops: serde_json::Value = from_value(patch.clone()).unwrap();
+    assert_eq!(
+        ops,
+        json!([
+            { "op": "remove", "path": "menu.legacy" },
+            { "op": "add", "path": "menu.close", "value": "Close" }
+        ])
+    );
+    i18n.apply_patch("en", patch).unwrap();
+    assert!(i18n.has_translation("en", "menu.close"));
+    assert!(!i18n.has_translation("en", "menu.legacy"));
+
+    let failing = serde_wasm_bindgen::to_value(&json!([
+        { "op": "add", "path": "menu.help", "value": "Help" },
+        { "op": "remove", "path": "menu.legacy" }
+    ]))
+    .unwrap();
+    assert!(i18n.apply_patch("en", failing).is_err());
+    assert!(!i18n.has_translation("en", "menu.help"));
+}
+
+#[wasm_bindgen_test]
+fn test_merge_strategies() {
+    let i18n = I18n::new();
+    let set = |tree: serde_json::Value, options: serde_json::Value| {
+        i18n.set_translations_with_options("en", serde_wasm_bindgen::to_value(&tree).unwrap(), serde_wasm_bindgen::to_value(&options).unwrap())
+    };
+    let all = |i18n: &I18n| -> serde_json::Value { from_value(i18n.get_translations("en").unwrap()).unwrap() };
+
+    set(json!({ "menu": { "open": "Open", "close": "Close" }, "title": "Title" }), json!({})).unwrap();
+    set(json!({ "menu": { "open": "Open…", "help": "Help" } }), json!({ "strategy": "keep-existing" })).unwrap();
+    assert_eq!(all(&i18n), json!({ "menu": { "open": "Open", "close": "Close", "help": "Help" }, "title": "Title" }));
+
+    set(json!({ "menu": { "open": "Open…" } }), json!({ "strategy": "shallow" })).unwrap();
+    assert_eq!(all(&i18n), json!({ "menu": { "open": "Open…" }, "title": "Title" }));
+
+    let err = set(json!({ "title": { "short": "T" } }), json!({ "onConflict": "error" })).unwrap_err();
+    assert_eq!(err.as_string().unwrap(), "Type conflicts at 'title'");
+    assert_eq!(all(&i18n), json!({ "menu": { "open": "Open…" }, "title": "Title" }));
+
+    set(json!({ "title": { "short": "T" } }), json!({})).unwrap();
+    assert!(i18n.has_translation("en", "title.short"));
+
+    set(json!({ "hello": "Hello" }), json!({ "strategy": "replace" })).unwrap();
+    assert_eq!(all(&i18n), json!({ "hello": "Hello" }));
+}
+
+#[wasm_bindgen_test]
+fn test_merge_strategies_drop_stale_metadata() {
+    let i18n = I18n::new();
+    i18n.set_history_enabled(true, None);
+    let set = |tree: serde_json::Value, options: serde_json::Value| {
+        i18n.set_translations_with_options("en", serde_wasm_bindgen::to_value(&tree).unwrap(), serde_wasm_bindgen::to_value(&options).unwrap())
+    };
+    let describe = |key: &str| {
+        i18n.set_metadata("en", key, serde_wasm_bindgen::to_value(&json!({ "description": key })).unwrap()).unwrap()
+    };
+    let described = |key: &str| !i18n.get_metadata("en", key).unwrap().is_undefined();
+
+    set(json!({ "menu": { "open": "Open", "close": "Close" }, "title": "Title", "help": { "about": "About" } }), json!({})).unwrap();
+    describe("menu.open");
+    describe("menu.close");
+    describe("title");
+    describe("help.about");
+
+    set(json!({ "menu": { "open": "Open…" } }), json!({ "strategy": "shallow" })).unwrap();
+    assert!(!described("menu.open"));
+    assert!(!described("menu.close"));
+    assert!(described("title"));
+
+    set(json!({ "help": "Help" }), json!({})).unwrap();
+    assert!(!described("help.about"));
+
+    set(json!({ "hello": "Hello" }), json!({ "strategy": "replace" })).unwrap();
+    assert!(!described("title"));
+    let xliff = i18n.export_xliff("en", "en", None).unwrap();
+    assert!(!xliff.contains("<note"));
+
+    assert!(i18n.undo().unwrap());
+    assert!(described("title"));
+}
+
+#[wasm_bindgen_test]
+fn test_del_translation_missing_and_prune() {
+    let i18n = I18n::new();
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({ "menu": { "file": { "open": "Open" } }, "title": "Title" })).unwrap(),
+    )
+    .unwrap();
+    let all = |i18n: &I18n| -> serde_json::Value { from_value(i18n.get_translations("en").unwrap()).unwrap() };
+
+    assert!(!i18n.del_translation("en", "a.b.c").unwrap());
+    assert!(!i18n.del_translation("en", "title.short").unwrap());
+    assert!(!i18n.del_translation_with_options("en", "menu.file.close", Some(true)).unwrap());
+    assert_eq!(all(&i18n), json!({ "menu": { "file": { "open": "Open" } }, "title": "Title" }));
+
+    assert!(i18n.del_translation_with_options("en", "menu.file.open", Some(true)).unwrap());
+    assert_eq!(all(&i18n), json!({ "title": "Title" }));
+
+    assert!(i18n.del_translation("en", "title").unwrap());
+    assert!(!i18n.del_translation("en", "title").unwrap());
+}
+
+#[wasm_bindgen_test]
+fn test_keys_search_and_counts() {
+    let i18n = I18n::new();
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({ "menu": { "open": "Open", "close": "Close" }, "menuBar": "Bar", "title": "Open file" }))
+            .unwrap(),
+    )
+    .unwrap();
+    i18n.set_translations("de", serde_wasm_bindgen::to_value(&json!({ "menu": { "open": "Öffnen" } })).unwrap())
+        .unwrap();
+
+    let keys: Vec<String> = from_value(i18n.keys("en", None).unwrap()).unwrap();
+    assert_eq!(keys, vec!["menu.close", "menu.open", "menuBar", "title"]);
+    let keys: Vec<String> = from_value(i18n.keys("en", Some("menu".to_string())).unwrap()).unwrap();
+    assert_eq!(keys, vec!["menu.close", "menu.open"]);
+
+    let options = serde_wasm_bindgen::to_value(&json!({ "ignoreCase": true })).unwrap();
+    let found: serde_json::Value = from_value(i18n.search("en", "OPEN", options).unwrap()).unwrap();
+    assert_eq!(found, json!([{ "key": "menu.open", "value": "Open" }, { "key": "title", "value": "Open file" }]));
+
+    let options = serde_wasm_bindgen::to_value(&json!({ "regex": true, "scope": "keys" })).unwrap();
+    let found: serde_json::Value = from_value(i18n.search("en", r"^menu\.c", options).unwrap()).unwrap();
+    assert_eq!(found, json!([{ "key": "menu.close", "value": "Close" }]));
+
+    let options = serde_wasm_bindgen::to_value(&json!({ "regex": true })).unwrap();
+    assert!(i18n.search("en", "(", options).is_err());
+
+    let counts: serde_json::Value = from_value(i18n.key_counts().unwrap()).unwrap();
+    assert_eq!(counts, json!({ "de": 1, "en": 4 }));
+}
+
+#[wasm_bindgen_test]
+fn test_coverage() {
+    let i18n = I18n::new();
+    i18n.set_translations(
+        "en",
+        serde_wasm_bindgen::to_value(&json!({
+            "menu": { "open": "Open file", "close": "Close" },
+            "cart": { "one": "{count} item", "other": "{count} items" },
+            "title": "The title"
+        }))
+        .unwrap(),
+    )
+    .unwrap();
+    i18n.set_translations(
+        "pl",
+        serde_wasm_bindgen::to_value(&json!({ "menu": { "open": "Otwórz plik", "close": "" }, "cart": { "other": "{count} rzeczy" } }))
+            .unwrap(),
+    )
+    .unwrap();
+
+    let report: serde_json::Value = from_value(i18n.coverage("en").unwrap()).unwrap();
+    assert_eq!(report["totalKeys"], 4);
+    assert_eq!(report["totalWords"], 9);
+    assert_eq!(report["locales"]["en"]["percent"], 100.0);
+
+    let pl = &report["locales"]["pl"];
+    assert_eq!(pl["translatedKeys"], 2);
+    assert_eq!(pl["percent"], 50.0);
+    assert_eq!(pl["untranslatedWords"], 3);
+    assert_eq!(pl["untranslated"], json!(["menu.close", "title"]));
+    assert_eq!(pl["namespaces"]["cart"]["percent"], 100.0);
+    assert_eq!(pl["namespaces"]["menu"]["untranslated"], json!(["menu.close"]));
+
+    // A map where the source has a string is a conflict, not a translation,
+    // unless it holds plural forms.
+    i18n.set_translations(
+        "de",
+        serde_wasm_bindgen::to_value(&json!({ "menu": { "open": { "short": "Öffnen" }, "close": { "one": "Schließen", "other": "Schließen" } } }))
+            .unwrap(),
+    )
+    .unwrap();
+    let report: serde_json::Value = from_value(i18n.coverage("en").unwrap()).unwrap();
+    assert_eq!(report["locales"]["de"]["untranslated"], json!(["cart", "menu.open", "title"]));
+
+    assert!(i18n.coverage("fr").is_err());
+}